@@ -0,0 +1,845 @@
+use crate::control::command_executor::CommandExecutor;
+use crate::control::output::{self, OutputFormat};
+use crate::control::*;
+use crate::data::*;
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use serde::Serialize;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct Selection {
+    /// Name of the board to select before running the command
+    #[structopt(short, long)]
+    board: Option<String>,
+
+    /// Name of the list to select before running the command
+    #[structopt(short, long)]
+    list: Option<String>,
+
+    /// Name of the card to select before running the command
+    #[structopt(long)]
+    card: Option<String>,
+
+    /// Name of the checklist to select before running the command
+    #[structopt(long)]
+    checklist: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Board commands
+    Board(BoardCommand),
+    /// Label commands (requires --board)
+    Label(LabelCommand),
+    /// List commands (requires --board)
+    List(ListCommand),
+    /// Card commands (requires --board and --list)
+    Card(CardCommand),
+    /// Checklist commands (requires --board, --list and --card)
+    Checklist(ChecklistCommand),
+    /// Sync commands
+    Sync(SyncCommand),
+    /// Local store commands
+    Db(DbCommand),
+    /// Full-screen kanban view of a board
+    Tui {
+        /// Board to open. Without it the TUI starts with a list of all boards
+        #[structopt(short, long)]
+        board: Option<String>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum BoardCommand {
+    GetAll,
+    CreateNew { name: String },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum SyncCommand {
+    /// Send the changes made while Trello could not be reached, in the order they were made
+    Push,
+    /// Copy every list, card, label, checklist, task and comment of a board to the local store. After the first time only what changed is read
+    Board {
+        name: String,
+        /// Read the whole board again instead of only what changed since the last sync
+        #[structopt(long)]
+        full: bool,
+    },
+    /// Copy everything on every board to the local store
+    All {
+        /// Read the whole boards again instead of only what changed since the last sync
+        #[structopt(long)]
+        full: bool,
+    },
+    /// Delete the local copies of items that were deleted or archived in Trello
+    Purge,
+    /// List the card and task fields that were changed both locally and on Trello, or pick a side for them
+    Conflicts {
+        /// Number of the conflict to resolve, as listed
+        number: Option<usize>,
+        /// Which value to keep: local or trello
+        side: Option<String>,
+        /// Fields to keep that value for. Without any, it is kept for every field of the conflict
+        fields: Vec<String>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum DbCommand {
+    /// Upgrade the data in the MongoDB store to the schema this version writes
+    Migrate {
+        /// List the migrations that would run and how many documents each changes, without changing anything
+        #[structopt(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum LabelCommand {
+    GetAll {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    Create {
+        #[structopt(flatten)]
+        selection: Selection,
+        name: String,
+        color: String,
+    },
+    Delete {
+        #[structopt(flatten)]
+        selection: Selection,
+        name: String,
+    },
+    Update {
+        #[structopt(flatten)]
+        selection: Selection,
+        name: String,
+        color: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ListCommand {
+    GetAll {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    Create {
+        #[structopt(flatten)]
+        selection: Selection,
+        name: String,
+    },
+    DueDates {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum CardCommand {
+    GetAll {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    Create {
+        #[structopt(flatten)]
+        selection: Selection,
+        name: String,
+    },
+    GetDescription {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    EditDescription {
+        #[structopt(flatten)]
+        selection: Selection,
+        description: String,
+    },
+    MoveToList {
+        #[structopt(flatten)]
+        selection: Selection,
+        list_name: String,
+    },
+    GetLabels {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    AddLabel {
+        #[structopt(flatten)]
+        selection: Selection,
+        label_name: String,
+    },
+    RemoveLabel {
+        #[structopt(flatten)]
+        selection: Selection,
+        label_name: String,
+    },
+    GetDueDate {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    /// Due date in the format "yyyy-mm-dd hh:mm:ss"
+    SetDueDate {
+        #[structopt(flatten)]
+        selection: Selection,
+        due_date: String,
+    },
+    SetDueComplete {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    GetComments {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    AddComment {
+        #[structopt(flatten)]
+        selection: Selection,
+        text: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ChecklistCommand {
+    GetAll {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    Create {
+        #[structopt(flatten)]
+        selection: Selection,
+        name: String,
+    },
+    GetTasks {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    AddTask {
+        #[structopt(flatten)]
+        selection: Selection,
+        name: String,
+    },
+    CompleteTask {
+        #[structopt(flatten)]
+        selection: Selection,
+        name: String,
+    },
+}
+
+struct CommandLine {
+    command_exec: CommandExecutor,
+    output_format: OutputFormat,
+
+    current_card: Option<Card>,
+}
+
+impl CommandLine {
+    fn failed(&self, message: &str) -> CommandResultCode {
+        let result: CommandResult<()> = CommandResult {
+            result_code: CommandResultCode::Failed,
+            result: None,
+            result_string: Some(message.to_string()),
+            last_synced_instant_seconds: None,
+        };
+        self.report(&result);
+        CommandResultCode::Failed
+    }
+
+    fn report<T: Serialize>(&self, result: &CommandResult<T>) {
+        if self.output_format != OutputFormat::Text {
+            output::print_structured(self.output_format, result);
+            return;
+        }
+
+        let message = result.result_string.clone().unwrap_or_default();
+        match result.result_code {
+            CommandResultCode::Success => println!("{}", message),
+            CommandResultCode::Failed => eprintln!("{}", message),
+        }
+        if let Some(last_synced) = result.last_synced_instant_seconds {
+            eprintln!("{}", output::stale_note(last_synced));
+        }
+    }
+
+    fn shows_details<T>(&self, result: &CommandResult<T>) -> bool {
+        self.output_format == OutputFormat::Text && matches!(result.result_code, CommandResultCode::Success)
+    }
+
+    fn report_card(&self, card: Card, text: String) -> CommandResultCode {
+        if self.output_format == OutputFormat::Text {
+            println!("{}", text);
+        } else {
+            let result = CommandResult {
+                result_code: CommandResultCode::Success,
+                result: Some(card),
+                result_string: Some(String::from("Retrieved card")),
+                last_synced_instant_seconds: None,
+            };
+            self.report(&result);
+        }
+        CommandResultCode::Success
+    }
+
+    async fn run(mut self, command: Command) -> CommandResultCode {
+        match command {
+            Command::Board(board_command) => self.run_board_command(board_command).await,
+            Command::Label(label_command) => self.run_label_command(label_command).await,
+            Command::List(list_command) => self.run_list_command(list_command).await,
+            Command::Card(card_command) => self.run_card_command(card_command).await,
+            Command::Checklist(checklist_command) => self.run_checklist_command(checklist_command).await,
+            Command::Sync(sync_command) => self.run_sync_command(sync_command).await,
+            Command::Db(db_command) => self.run_db_command(db_command).await,
+            Command::Tui { board } => tui::run(self.command_exec, board).await,
+        }
+    }
+
+    async fn apply_selection(&mut self, selection: &Selection) -> CommandResultCode {
+        if let Some(board_name) = &selection.board {
+            let board_result = self.command_exec.select_board(board_name).await;
+            if let CommandResultCode::Failed = board_result.result_code {
+                self.report(&board_result);
+                return CommandResultCode::Failed;
+            }
+        }
+
+        if let Some(list_name) = &selection.list {
+            let list_result = self.command_exec.select_board_list(list_name, None).await;
+            if let CommandResultCode::Failed = list_result.result_code {
+                self.report(&list_result);
+                return CommandResultCode::Failed;
+            }
+        }
+
+        if let Some(card_name) = &selection.card {
+            let card_result = self.command_exec.select_list_card(card_name, None).await;
+            match card_result.result_code {
+                CommandResultCode::Success => {
+                    self.current_card = card_result.result;
+                }
+
+                CommandResultCode::Failed => {
+                    self.report(&card_result);
+                    return CommandResultCode::Failed;
+                }
+            }
+        }
+
+        if let Some(checklist_name) = &selection.checklist {
+            let checklist_result = self.command_exec.select_card_checklist(None, checklist_name).await;
+            if let CommandResultCode::Failed = checklist_result.result_code {
+                self.report(&checklist_result);
+                return CommandResultCode::Failed;
+            }
+        }
+
+        CommandResultCode::Success
+    }
+
+    async fn run_board_command(&mut self, command: BoardCommand) -> CommandResultCode {
+        match command {
+            BoardCommand::GetAll => {
+                let boards_result = self.command_exec.get_all_boards().await;
+                self.report(&boards_result);
+                if self.shows_details(&boards_result) {
+                    for board in boards_result.result.unwrap() {
+                        println!("  - {}", board.name);
+                    }
+                }
+                boards_result.result_code
+            }
+
+            BoardCommand::CreateNew { name } => {
+                let board_result = self.command_exec.create_board(&name).await;
+                self.report(&board_result);
+                board_result.result_code
+            }
+        }
+    }
+
+    async fn run_sync_command(&mut self, command: SyncCommand) -> CommandResultCode {
+        match command {
+            SyncCommand::Push => {
+                let push_result = self.command_exec.push_outbox().await;
+                self.report(&push_result);
+                push_result.result_code
+            }
+            SyncCommand::Board { name, full } => {
+                let sync_result = self.command_exec.sync_board(&name, full).await;
+                self.report(&sync_result);
+                sync_result.result_code
+            }
+            SyncCommand::All { full } => {
+                let sync_result = self.command_exec.sync_all_boards(full).await;
+                self.report(&sync_result);
+                if self.shows_details(&sync_result) {
+                    for snapshot in sync_result.result.as_ref().unwrap() {
+                        println!("  {}: {}", snapshot.board.name, snapshot.describe());
+                    }
+                }
+                sync_result.result_code
+            }
+            SyncCommand::Purge => {
+                let purge_result = self.command_exec.purge_tombstones().await;
+                self.report(&purge_result);
+                purge_result.result_code
+            }
+            SyncCommand::Conflicts { number: None, .. } => {
+                let conflicts_result = self.command_exec.get_conflicts().await;
+                self.report(&conflicts_result);
+                if self.shows_details(&conflicts_result) {
+                    for (index, conflict) in conflicts_result.result.as_ref().unwrap().iter().enumerate() {
+                        println!("  {}", output::describe_conflict(index + 1, conflict));
+                    }
+                }
+                conflicts_result.result_code
+            }
+            SyncCommand::Conflicts { number: Some(number), side, fields } => {
+                let side = match side {
+                    Some(side) => side,
+                    None => return self.failed(&format!("Pick a side for conflict {}: local or trello", number)),
+                };
+                let resolve_result = self.command_exec.resolve_conflict(number, &side, &fields).await;
+                self.report(&resolve_result);
+                resolve_result.result_code
+            }
+        }
+    }
+
+    async fn run_db_command(&mut self, command: DbCommand) -> CommandResultCode {
+        match command {
+            DbCommand::Migrate { dry_run } => {
+                let migrate_result = self.command_exec.migrate_local_store(dry_run).await;
+                self.report(&migrate_result);
+                if self.shows_details(&migrate_result) {
+                    for step in &migrate_result.result.as_ref().unwrap().steps {
+                        println!("  {}", step.describe());
+                    }
+                }
+                migrate_result.result_code
+            }
+        }
+    }
+
+    async fn run_label_command(&mut self, command: LabelCommand) -> CommandResultCode {
+        match command {
+            LabelCommand::GetAll { selection } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let labels_result = self.command_exec.get_all_board_labels(None).await;
+                self.report(&labels_result);
+                if self.shows_details(&labels_result) {
+                    for label in labels_result.result.unwrap() {
+                        println!("  {name}: {color}", name = label.name, color = label.color);
+                    }
+                }
+                labels_result.result_code
+            }
+
+            LabelCommand::Create { selection, name, color } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let create_result = self.command_exec.create_board_label(None, &name, &color).await;
+                self.report(&create_result);
+                create_result.result_code
+            }
+
+            LabelCommand::Delete { selection, name } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let delete_result = self.command_exec.delete_board_label(None, &name).await;
+                self.report(&delete_result);
+                delete_result.result_code
+            }
+
+            LabelCommand::Update { selection, name, color } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let update_result = self.command_exec.update_board_label(None, &name, &color).await;
+                self.report(&update_result);
+                update_result.result_code
+            }
+        }
+    }
+
+    async fn run_list_command(&mut self, command: ListCommand) -> CommandResultCode {
+        match command {
+            ListCommand::GetAll { selection } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let lists_result = self.command_exec.get_all_board_lists(None).await;
+                self.report(&lists_result);
+                if self.shows_details(&lists_result) {
+                    for list in lists_result.result.unwrap() {
+                        println!("  - {}", list.name);
+                    }
+                }
+                lists_result.result_code
+            }
+
+            ListCommand::Create { selection, name } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let list_result = self.command_exec.create_board_list(None, &name).await;
+                self.report(&list_result);
+                list_result.result_code
+            }
+
+            ListCommand::DueDates { selection } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let cards_result = self.command_exec.get_all_list_cards(None).await;
+                self.report(&cards_result);
+                if self.shows_details(&cards_result) {
+                    for card in cards_result.result.unwrap() {
+                        println!("  - {due}\n      {name}\n", due = format_due_date(card.due_date_instant_seconds), name = card.name);
+                    }
+                }
+                cards_result.result_code
+            }
+        }
+    }
+
+    async fn run_card_command(&mut self, command: CardCommand) -> CommandResultCode {
+        match command {
+            CardCommand::GetAll { selection } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let cards_result = self.command_exec.get_all_list_cards(None).await;
+                self.report(&cards_result);
+                if self.shows_details(&cards_result) {
+                    for card in cards_result.result.unwrap() {
+                        println!("  - {}", card.name);
+                    }
+                }
+                cards_result.result_code
+            }
+
+            CardCommand::Create { selection, name } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let card_result = self.command_exec.create_list_card(None, &name).await;
+                self.report(&card_result);
+                card_result.result_code
+            }
+
+            CardCommand::GetDescription { selection } => {
+                let card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let description = card.description.clone();
+                self.report_card(card, description)
+            }
+
+            CardCommand::EditDescription { selection, description } => {
+                let mut card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                card.description = description;
+                let card_result = self.command_exec.update_card(&card).await;
+                self.report(&card_result);
+                card_result.result_code
+            }
+
+            CardCommand::MoveToList { selection, list_name } => {
+                let card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let card_result = self.command_exec.move_card_to_list(card, &list_name).await;
+                self.report(&card_result);
+                card_result.result_code
+            }
+
+            CardCommand::GetLabels { selection } => {
+                let card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let labels_result = self.command_exec.get_card_labels(&card).await;
+                self.report(&labels_result);
+                if self.shows_details(&labels_result) {
+                    for label in labels_result.result.unwrap() {
+                        println!("  {name}: {color}", name = label.name, color = label.color);
+                    }
+                }
+                labels_result.result_code
+            }
+
+            CardCommand::AddLabel { selection, label_name } => {
+                let card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let card_result = self.command_exec.add_card_label(card, &label_name).await;
+                self.report(&card_result);
+                card_result.result_code
+            }
+
+            CardCommand::RemoveLabel { selection, label_name } => {
+                let card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let card_result = self.command_exec.remove_card_label(card, &label_name).await;
+                self.report(&card_result);
+                card_result.result_code
+            }
+
+            CardCommand::GetDueDate { selection } => {
+                let card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let due_date = format_due_date(card.due_date_instant_seconds);
+                self.report_card(card, due_date)
+            }
+
+            CardCommand::SetDueDate { selection, due_date } => {
+                let mut card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let due_datetime = NaiveDateTime::parse_from_str(&due_date, "%Y-%m-%d %H:%M:%S")
+                    .ok()
+                    .and_then(|naive| Local.from_local_datetime(&naive).single());
+                match due_datetime {
+                    Some(due_datetime) => {
+                        card.due_date_instant_seconds = due_datetime.timestamp();
+                        let card_result = self.command_exec.update_card(&card).await;
+                        self.report(&card_result);
+                        card_result.result_code
+                    }
+
+                    None => self.failed("Unable to parse the due date given"),
+                }
+            }
+
+            CardCommand::SetDueComplete { selection } => {
+                let mut card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                card.due_complete = true;
+                let card_result = self.command_exec.update_card(&card).await;
+                self.report(&card_result);
+                card_result.result_code
+            }
+
+            CardCommand::GetComments { selection } => {
+                let card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let comments_result = self.command_exec.get_card_comments(Some(card)).await;
+                self.report(&comments_result);
+                if self.shows_details(&comments_result) {
+                    for comment in comments_result.result.unwrap() {
+                        println!("{name} - {date}\n  {text}\n", name = comment.commenter_name, date = format_due_date(comment.comment_time_instant_seconds), text = comment.text);
+                    }
+                }
+                comments_result.result_code
+            }
+
+            CardCommand::AddComment { selection, text } => {
+                let card = match self.selected_card(&selection).await {
+                    Some(card) => card,
+                    None => return CommandResultCode::Failed,
+                };
+                let comment_result = self.command_exec.add_card_comment(Some(card), &text).await;
+                self.report(&comment_result);
+                comment_result.result_code
+            }
+        }
+    }
+
+    async fn run_checklist_command(&mut self, command: ChecklistCommand) -> CommandResultCode {
+        match command {
+            ChecklistCommand::GetAll { selection } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let checklists_result = self.command_exec.get_card_checklists(None).await;
+                self.report(&checklists_result);
+                if self.shows_details(&checklists_result) {
+                    for checklist in checklists_result.result.unwrap() {
+                        println!("  - {}", checklist.name);
+                    }
+                }
+                checklists_result.result_code
+            }
+
+            ChecklistCommand::Create { selection, name } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let checklist_result = self.command_exec.create_card_checklists(None, &name).await;
+                self.report(&checklist_result);
+                checklist_result.result_code
+            }
+
+            ChecklistCommand::GetTasks { selection } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let tasks_result = self.command_exec.get_checklist_tasks(None).await;
+                self.report(&tasks_result);
+                if self.shows_details(&tasks_result) {
+                    for task in tasks_result.result.unwrap() {
+                        let complete = if task.is_complete { "complete" } else { "incomplete" };
+                        println!("  - [{complete}] {name}", complete = complete, name = task.name);
+                    }
+                }
+                tasks_result.result_code
+            }
+
+            ChecklistCommand::AddTask { selection, name } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let task_result = self.command_exec.create_checklist_task(None, &name).await;
+                self.report(&task_result);
+                task_result.result_code
+            }
+
+            ChecklistCommand::CompleteTask { selection, name } => {
+                if let CommandResultCode::Failed = self.apply_selection(&selection).await {
+                    return CommandResultCode::Failed;
+                }
+                let tasks_result = self.command_exec.get_checklist_tasks(None).await;
+                if let CommandResultCode::Failed = tasks_result.result_code {
+                    self.report(&tasks_result);
+                    return CommandResultCode::Failed;
+                }
+
+                let task = tasks_result.result.unwrap().into_iter().find(|task| task.name.eq_ignore_ascii_case(&name));
+                match task {
+                    Some(mut task) => {
+                        task.is_complete = true;
+                        let task_result = self.command_exec.update_checklist_task(None, task).await;
+                        self.report(&task_result);
+                        task_result.result_code
+                    }
+
+                    None => self.failed("Could not find a task with the given name"),
+                }
+            }
+        }
+    }
+
+    async fn selected_card(&mut self, selection: &Selection) -> Option<Card> {
+        if let CommandResultCode::Failed = self.apply_selection(selection).await {
+            return None;
+        }
+
+        if self.current_card.is_none() {
+            self.failed("No card has been selected. Use --board, --list and --card to choose one");
+        }
+        self.current_card.clone()
+    }
+}
+
+pub(crate) fn format_due_date(instant_seconds: i64) -> String {
+    if instant_seconds == 0 {
+        "No Due Date".to_string()
+    } else {
+        Local.timestamp_opt(instant_seconds, 0).unwrap().to_rfc2822()
+    }
+}
+
+pub async fn run(config: Option<serde_json::Value>, command: Command, output_format: OutputFormat) -> CommandResultCode {
+    let command_exec = match CommandExecutor::new(config).await {
+        Ok(command_exec) => command_exec,
+        Err(why) => {
+            eprintln!("Failed to set up the data stores: {}", why);
+            return CommandResultCode::Failed;
+        }
+    };
+    let command_line = CommandLine {
+        command_exec,
+        output_format,
+        current_card: None,
+    };
+    command_line.run(command).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn command_line(output_format: OutputFormat) -> CommandLine {
+        let config = Some(json!({ "stores": { "remote": "memory", "local": "memory" } }));
+        let mut command_exec = CommandExecutor::new(config).await.unwrap();
+        command_exec.create_board("Eng").await;
+        command_exec.select_board("Eng").await;
+        command_exec.create_board_list(None, "Todo").await;
+        command_exec.select_board_list("Todo", None).await;
+        command_exec.create_list_card(None, "Fix login").await;
+        CommandLine {
+            command_exec,
+            output_format,
+            current_card: None,
+        }
+    }
+
+    fn selection(board: &str, list: Option<&str>, card: Option<&str>) -> Selection {
+        Selection {
+            board: Some(board.to_string()),
+            list: list.map(String::from),
+            card: card.map(String::from),
+            checklist: None,
+        }
+    }
+
+    #[test]
+    fn failures_exit_with_a_non_zero_status() {
+        assert_eq!(CommandResultCode::Success.exit_code(), 0);
+        assert_ne!(CommandResultCode::Failed.exit_code(), 0);
+    }
+
+    #[tokio::test]
+    async fn selections_resolve_board_list_and_card() {
+        let command_line = command_line(OutputFormat::Text).await;
+        let command = Command::Checklist(ChecklistCommand::GetAll {
+            selection: selection("Eng", Some("Todo"), Some("Fix login")),
+        });
+        assert!(matches!(command_line.run(command).await, CommandResultCode::Success));
+    }
+
+    #[tokio::test]
+    async fn selections_with_unknown_names_fail() {
+        let unknown_board = Command::List(ListCommand::GetAll { selection: selection("Ops", None, None) });
+        assert!(matches!(command_line(OutputFormat::Text).await.run(unknown_board).await, CommandResultCode::Failed));
+
+        let unknown_list = Command::Card(CardCommand::GetAll { selection: selection("Eng", Some("Done"), None) });
+        assert!(matches!(command_line(OutputFormat::Text).await.run(unknown_list).await, CommandResultCode::Failed));
+
+        let unknown_card = Command::Card(CardCommand::GetDescription {
+            selection: selection("Eng", Some("Todo"), Some("Fix logout")),
+        });
+        assert!(matches!(command_line(OutputFormat::Text).await.run(unknown_card).await, CommandResultCode::Failed));
+    }
+
+    #[tokio::test]
+    async fn json_output_reports_the_result_code() {
+        let found = Command::Card(CardCommand::GetAll { selection: selection("Eng", Some("Todo"), None) });
+        assert!(matches!(command_line(OutputFormat::Json).await.run(found).await, CommandResultCode::Success));
+
+        let missing = Command::Card(CardCommand::GetAll { selection: selection("Eng", Some("Done"), None) });
+        assert!(matches!(command_line(OutputFormat::Json).await.run(missing).await, CommandResultCode::Failed));
+    }
+}
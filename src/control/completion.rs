@@ -0,0 +1,183 @@
+use crate::control::interactive_cli::*;
+use crate::data::CachedNames;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+/**
+ * Line editor helper for the interactive shell. Completes shell commands, their subcommands
+ * and the names of boards, lists, cards, labels, checklists and tasks the shell already knows about.
+ */
+#[derive(Default)]
+pub struct ShellHelper {
+    names: CachedNames,
+    depth: usize,
+}
+
+impl ShellHelper {
+    /**
+     * `depth` is how many levels below the root the shell currently is, used to complete `cd` paths.
+     */
+    pub fn set_names(&mut self, names: CachedNames, depth: usize) {
+        self.names = names;
+        self.depth = depth;
+    }
+}
+
+fn subcommands(command: &str) -> &'static [&'static str] {
+    match command {
+        "board" => BOARD_COMMANDS,
+        "label" => LABEL_COMMANDS,
+        "list" => LIST_COMMANDS,
+        "card" => CARD_COMMANDS,
+        "checklist" => CHECKLIST_COMMANDS,
+        "sync" => SYNC_COMMANDS,
+        "db" => DB_COMMANDS,
+        _ => &[],
+    }
+}
+
+fn argument_names<'a>(names: &'a CachedNames, command: &str, subcommand: &str) -> &'a Option<Vec<String>> {
+    match (command, subcommand) {
+        ("board", "select") | ("sync", "board") => &names.boards,
+        ("list", "select") | ("card", "move-to-list") => &names.lists,
+        ("card", "select") => &names.cards,
+        ("label", "delete") | ("card", "add-label") | ("card", "remove-label") => &names.labels,
+        ("checklist", "select") => &names.checklists,
+        ("checklist", "complete-task") => &names.tasks,
+        _ => &None,
+    }
+}
+
+fn child_names(names: &CachedNames, depth: usize) -> &Option<Vec<String>> {
+    match depth {
+        0 => &names.boards,
+        1 => &names.lists,
+        2 => &names.cards,
+        3 => &names.checklists,
+        _ => &None,
+    }
+}
+
+fn matching<'a, I: IntoIterator<Item = &'a str>>(candidates: I, prefix: &str, suffix: &str) -> Vec<Pair> {
+    let prefix = prefix.to_lowercase();
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&prefix))
+        .map(|candidate| Pair {
+            display: candidate.to_string(),
+            replacement: format!("{}{}", candidate, suffix),
+        })
+        .collect()
+}
+
+/**
+ * Returns the position the completion starts at and the candidates for the text before `pos`.
+ * The first word completes to a shell command, the second to one of that command's subcommands
+ * and everything after the subcommand to a name, so names containing spaces complete as a whole.
+ * The argument of `cd` completes to the children of the current node.
+ */
+pub fn complete_line(line: &str, pos: usize, names: &CachedNames, depth: usize) -> (usize, Vec<Pair>) {
+    let line = &line[..pos];
+    let command_start = line.len() - line.trim_start().len();
+    let command_end = line[command_start..]
+        .find(char::is_whitespace)
+        .map(|offset| command_start + offset);
+
+    let command_end = match command_end {
+        Some(end) => end,
+        None => return (command_start, matching(SHELL_COMMANDS.iter().copied(), &line[command_start..], " ")),
+    };
+
+    let command = &line[command_start..command_end];
+    let rest = &line[command_end..];
+    let subcommand_start = command_end + rest.len() - rest.trim_start().len();
+    if command == "cd" {
+        let candidates = match child_names(names, depth) {
+            Some(candidates) => matching(candidates.iter().map(|name| name.as_str()), &line[subcommand_start..], ""),
+            None => vec![],
+        };
+        return (subcommand_start, candidates);
+    }
+
+    let subcommand_end = line[subcommand_start..]
+        .find(char::is_whitespace)
+        .map(|offset| subcommand_start + offset);
+
+    let subcommand_end = match subcommand_end {
+        Some(end) => end,
+        None => {
+            let subcommand_names = subcommands(command)
+                .iter()
+                .map(|usage| usage.split_whitespace().next().unwrap_or(""));
+            return (subcommand_start, matching(subcommand_names, &line[subcommand_start..], " "));
+        }
+    };
+
+    let subcommand = &line[subcommand_start..subcommand_end];
+    let rest = &line[subcommand_end..];
+    let argument_start = subcommand_end + rest.len() - rest.trim_start().len();
+    let candidates = match argument_names(names, command, subcommand) {
+        Some(candidates) => matching(candidates.iter().map(|name| name.as_str()), &line[argument_start..], ""),
+        None => vec![],
+    };
+
+    (argument_start, candidates)
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(complete_line(line, pos, &self.names, self.depth))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replacements(line: &str, names: &CachedNames) -> (usize, Vec<String>) {
+        let (start, pairs) = complete_line(line, line.len(), names, 2);
+        (start, pairs.into_iter().map(|pair| pair.replacement).collect())
+    }
+
+    #[test]
+    fn completes_commands_and_subcommands() {
+        let names = CachedNames::default();
+        assert_eq!(replacements("ch", &names), (0, vec![String::from("checklist ")]));
+        assert_eq!(
+            replacements("card get-d", &names),
+            (5, vec![String::from("get-description "), String::from("get-due-date ")])
+        );
+    }
+
+    #[test]
+    fn completes_names_case_insensitively() {
+        let names = CachedNames {
+            cards: Some(vec![String::from("Fix login page"), String::from("Find flaky test"), String::from("Deploy")]),
+            ..CachedNames::default()
+        };
+
+        assert_eq!(
+            replacements("card select fi", &names),
+            (12, vec![String::from("Fix login page"), String::from("Find flaky test")])
+        );
+        assert_eq!(replacements("card select Fix lo", &names), (12, vec![String::from("Fix login page")]));
+        assert_eq!(replacements("list select Fi", &names), (12, vec![]));
+        assert_eq!(replacements("cd fix", &names), (3, vec![String::from("Fix login page")]));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::control::tokenizer::Arguments;
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_EDITOR: &str = "vi";
+/** How many names are tried for the temporary file before giving up */
+const TEMP_FILE_ATTEMPTS: u32 = 100;
+
+/**
+ * Lets the user edit `initial` in their editor (`$VISUAL`, then `$EDITOR`, then `vi`).
+ * Returns `None` when the text was saved unchanged.
+ */
+pub fn edit_text(initial: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from(DEFAULT_EDITOR));
+    edit_text_with(&editor, initial)
+}
+
+/**
+ * Same as `edit_text` with an explicit editor command line, e.g. `code --wait`.
+ */
+pub fn edit_text_with(editor: &str, initial: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut editor_args = Arguments::parse(editor)?;
+    let program = match editor_args.next() {
+        Some(program) => program,
+        None => return Err("No editor configured. Set the EDITOR environment variable".into()),
+    };
+
+    let path = create_temp_file(initial)?;
+
+    let status = Command::new(&program).args(editor_args).arg(&path).status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    let status = status.map_err(|why| format!("Failed to start editor '{}': {}", program, why))?;
+    if !status.success() {
+        return Err(format!("Editor '{}' exited with {}", program, status).into());
+    }
+
+    // Most editors end the file with a newline the user never typed
+    let edited = edited?;
+    let edited = edited.strip_suffix('\n').unwrap_or(&edited);
+    if edited == initial.strip_suffix('\n').unwrap_or(initial) {
+        Ok(None)
+    } else {
+        Ok(Some(edited.to_string()))
+    }
+}
+
+/**
+ * Writes `initial` to a new file in the temp dir that only the user can read. The file is always created,
+ * never opened, so a file or symlink someone else put there under the same name is not written through.
+ */
+fn create_temp_file(initial: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    for attempt in 0..TEMP_FILE_ATTEMPTS {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.subsec_nanos();
+        let path = env::temp_dir().join(format!("trello-cli-{}-{}-{}.md", std::process::id(), nanos, attempt));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        match options.open(&path) {
+            Ok(mut file) => {
+                file.write_all(initial.as_bytes())?;
+                return Ok(path);
+            }
+            Err(why) if why.kind() == ErrorKind::AlreadyExists => continue,
+            Err(why) => return Err(why.into()),
+        }
+    }
+    Err("Could not create a temporary file for the editor".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_edited_text_or_none_when_unchanged() {
+        assert_eq!(edit_text_with("true", "Spec").unwrap(), None);
+        assert_eq!(edit_text_with("sh -c 'echo Spec >> \"$0\"' ", "Spec").unwrap(), Some(String::from("SpecSpec")));
+        assert_eq!(edit_text_with("sh -c 'printf \"Line one\\nLine two\\n\" > \"$0\"'", "").unwrap(), Some(String::from("Line one\nLine two")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn temp_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = create_temp_file("Spec").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn failing_editor_is_an_error() {
+        assert!(edit_text_with("false", "Spec").is_err());
+        assert!(edit_text_with("", "Spec").is_err());
+    }
+}
@@ -1,23 +1,71 @@
 use crate::control::command_executor::CommandExecutor;
+use crate::control::completion::ShellHelper;
+use crate::control::editor;
+use crate::control::navigation;
+use crate::control::output::{self, OutputFormat};
+use crate::control::tokenizer::Arguments;
 use crate::control::*;
 use crate::data::*;
 
-use std::io::{self, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::convert::TryInto;
+use std::path::PathBuf;
 
 use chrono::{DateTime, TimeZone, Local};
+use rustyline::error::ReadlineError;
+use rustyline::{CompletionType, Config, EditMode, Editor};
+use serde::Serialize;
+
+const HISTORY_SIZE: usize = 1000;
+
+pub const SHELL_COMMANDS: &[&str] = &["board", "label", "list", "card", "checklist", "sync", "db", "cd", "ls", "pwd", "exit", "help"];
+pub const BOARD_COMMANDS: &[&str] = &["get-all", "select [<Name>]", "create-new <Name>", "help"];
+pub const LABEL_COMMANDS: &[&str] = &[
+    "get-all",
+    "create <Label_Name> <Label_Color>",
+    "delete [<Label_Name>]",
+    "update <Label_Name> <Label_Color>",
+    "help",
+];
+pub const LIST_COMMANDS: &[&str] = &["get-all", "select [<Name>]", "create <Name>", "due-dates", "help"];
+pub const CARD_COMMANDS: &[&str] = &[
+    "create <Name>",
+    "get-all",
+    "select [<Name>]",
+    "get-description",
+    "edit-description [<Text>]",
+    "move-to-list [<ListName>]",
+    "get-labels",
+    "add-label [<LabelName>]",
+    "remove-label [<LabelName>]",
+    "get-due-date",
+    "set-due-date <yyyy-mm-dd hh:mm:ss>",
+    "set-due-complete",
+    "get-comments",
+    "add-comment [<Text>]",
+    "help",
+];
+pub const SYNC_COMMANDS: &[&str] = &["push", "board", "all", "purge", "conflicts", "help"];
+pub const DB_COMMANDS: &[&str] = &["migrate", "help"];
+pub const CHECKLIST_COMMANDS: &[&str] = &["get-all", "select [<Name>]", "create <Name>", "get-tasks", "add-task <Name>", "complete-task [<Name>]", "help"];
 
 struct InteractiveCli {
     command_exec: CommandExecutor,
+    output_format: OutputFormat,
+    is_script: bool,
+    command_failed: bool,
 
     current_board: Option<Board>,
     current_list: Option<BoardList>,
     current_card: Option<Card>,
     current_checklist: Option<CardChecklist>,
+
+    /** The selection tab completion names were last fetched for, and the names it was offered */
+    completion_selection: Option<(Vec<String>, CachedNames)>,
 }
 
 impl InteractiveCli {
-    fn print_prompt(&mut self) {
+    fn prompt(&self) -> String {
         let mut path = String::from("");
         if self.current_board.is_some() {
             path.push_str(self.current_board.clone().unwrap().name.as_str());
@@ -36,41 +84,114 @@ impl InteractiveCli {
         }
 
         path.push('>');
-        print!("\n{}", path);
-        io::stdout().flush().unwrap();
+        path
+    }
+
+    fn print_result<T: Serialize>(&mut self, result: &CommandResult<T>) {
+        if let CommandResultCode::Failed = result.result_code {
+            self.command_failed = true;
+        }
+        output::print_structured(self.output_format, result);
+    }
+
+    /**
+     * Reports a failure that no command result was made for, in the selected output format.
+     */
+    fn print_error(&mut self, message: &str) {
+        let result: CommandResult<()> = CommandResult {
+            result_code: CommandResultCode::Failed,
+            result: None,
+            result_string: Some(message.to_string()),
+            last_synced_instant_seconds: None,
+        };
+        self.print_result(&result);
+    }
+
+    /**
+     * Prints text meant for the user rather than as output, e.g. help. Goes to stderr unless the output
+     * format is text, so JSON, YAML and TSV output stays parseable.
+     */
+    fn print_notice(&self, message: &str) {
+        if self.output_format == OutputFormat::Text {
+            println!("{}", message);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    fn shows_details<T>(&self, result: &CommandResult<T>) -> bool {
+        self.output_format == OutputFormat::Text && matches!(result.result_code, CommandResultCode::Success)
+    }
+
+    fn print_card(&mut self, card: Card) {
+        let result = CommandResult {
+            result_code: CommandResultCode::Success,
+            result: Some(card),
+            result_string: Some(String::from("Retrieved card")),
+            last_synced_instant_seconds: None,
+        };
+        self.print_result(&result);
     }
 
     fn print_invalid_command(&mut self, help: Option<String>) {
-        println!("Invalid command. {}", help.unwrap_or(String::from("")));
+        self.print_error(&format!("Invalid command. {}", help.unwrap_or(String::from(""))));
     }
 
     fn print_available_commands(&mut self, commands: &Vec<&str>) {
-        println!("Available commands: ");
+        self.print_notice("Available commands: ");
         for command in commands {
-            println!("  {}", command);
+            self.print_notice(&format!("  {}", command));
+        }
+    }
+
+    /**
+     * Opens the user's editor on `initial`. Returns `None` when there is nothing to save,
+     * either because the text was left unchanged or because the editor could not be used.
+     */
+    fn text_from_editor(&mut self, initial: &str) -> Option<String> {
+        if self.is_script {
+            self.print_invalid_command(Some(String::from("Text must be given on the command line when running a script")));
+            return None;
+        }
+
+        match editor::edit_text(initial) {
+            Ok(Some(text)) => Some(text),
+            Ok(None) => {
+                eprintln!("No changes made. Nothing was saved");
+                None
+            }
+            Err(why) => {
+                self.print_error(&why.to_string());
+                None
+            }
         }
     }
 
     fn get_selection_from_prompt(&mut self, names: &Vec<&str>) -> usize {
-        println!("Enter a number to choose one of the following options: ");
+        if self.is_script {
+            self.print_error("A name must be given when running a script");
+            return names.len();
+        }
+
+        self.print_notice("Enter a number to choose one of the following options: ");
         let mut count: i8 = 0;
         for name in names {
-            println!("  ({counter})  {name}", counter = count, name = name);
+            self.print_notice(&format!("  ({counter})  {name}", counter = count, name = name));
             count += 1;
         }
 
-        println!("Your selection (default = 0): ");
+        self.print_notice("Your selection (default = 0): ");
         let mut input = String::new();
         let read_result = io::stdin().read_line(&mut input);
         if read_result.is_err() {
-            println!("Error while reading input: {}", read_result.unwrap_err());
+            self.print_notice(&format!("Error while reading input: {}", read_result.unwrap_err()));
             return 0;
         } else if input.is_empty() {
             return 0;
         } else {
             let selection = input.trim().parse::<i32>();
             if selection.is_err() {
-                println!("Invalid input");
+                self.print_notice("Invalid input");
                 return 0;
             } 
             
@@ -82,38 +203,34 @@ impl InteractiveCli {
         }
     }
 
-    async fn handle_label_command(&mut self, mut input_iter: std::str::SplitAsciiWhitespace<'_>) {
-        let available_commands = vec![
-            "get-all",
-            "create <Label_Name> <Label_Color>",
-            "delete [<Label_Name>]",
-            "update <Label_Name> <Label_Color>",
-            "help",
-        ];
-        match input_iter.next().unwrap_or("") {
+    async fn handle_label_command(&mut self, mut args: Arguments) {
+        let available_commands = LABEL_COMMANDS.to_vec();
+        match args.next().unwrap_or_default().as_str() {
             "help" => self.print_available_commands(&available_commands),
 
             "get-all" => {
                 let labels_result = self.command_exec.get_all_board_labels(None).await;
-                println!("{}", labels_result.result_string.unwrap());
-                match labels_result.result_code {
-                    CommandResultCode::Success => {
-                        let labels: Vec<CardLabel> = labels_result.result.unwrap();
-                        println!("Labels: ");
-                        for label in labels {
-                            println!("  {name}: {color}", name = label.name, color = label.color);
+                self.print_result(&labels_result);
+                if self.output_format == OutputFormat::Text {
+                    match labels_result.result_code {
+                        CommandResultCode::Success => {
+                            let labels: Vec<CardLabel> = labels_result.result.unwrap();
+                            println!("Labels: ");
+                            for label in labels {
+                                println!("  {name}: {color}", name = label.name, color = label.color);
+                            }
                         }
-                    }
 
-                    CommandResultCode::Failed => {
-                        println!("Command Failed. Do you have a board selected?");
+                        CommandResultCode::Failed => {
+                            println!("Command Failed. Do you have a board selected?");
+                        }
                     }
                 }
             }
 
             "create" => {
-                let name = input_iter.next().unwrap_or("");
-                let color = input_iter.next().unwrap_or("");
+                let color = args.option("color").map(String::from).or_else(|| args.next_back()).unwrap_or_default();
+                let name = args.option("name").map(String::from).unwrap_or_else(|| args.remainder());
                 if name.is_empty() || color.is_empty() {
                     self.print_invalid_command(Some(String::from(
                         "You must provide both name and color",
@@ -122,19 +239,18 @@ impl InteractiveCli {
                 } else {
                     let create_result = self
                         .command_exec
-                        .create_board_label(None, name, color)
+                        .create_board_label(None, &name, &color)
                         .await;
-                    println!("{}", create_result.result_string.unwrap());
+                    self.print_result(&create_result);
                 }
             }
 
             "delete" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let mut label_name = remainder.join(" ");
+                let mut label_name = args.remainder();
                 if label_name.is_empty() {
                     let labels: Vec<CardLabel> = self.command_exec.get_all_board_labels(None).await.result.unwrap_or(vec![]);
                     if labels.is_empty() {
-                        println!("Found no labels to select");
+                        self.print_error("Found no labels to select");
                         return;
                     } else {
                         let label_names: Vec<&str> = labels.iter().map(|label| label.name.as_str()).collect::<Vec<_>>();
@@ -146,12 +262,12 @@ impl InteractiveCli {
                     .command_exec
                     .delete_board_label(None, &label_name)
                     .await;
-                println!("{}", delete_result.result_string.unwrap());
+                self.print_result(&delete_result);
             }
 
             "update" => {
-                let name = input_iter.next().unwrap_or("");
-                let color = input_iter.next().unwrap_or("");
+                let color = args.option("color").map(String::from).or_else(|| args.next_back()).unwrap_or_default();
+                let name = args.option("name").map(String::from).unwrap_or_else(|| args.remainder());
                 if name.is_empty() || color.is_empty() {
                     self.print_invalid_command(Some(String::from(
                         "You must provide both name and color",
@@ -160,9 +276,9 @@ impl InteractiveCli {
                 } else {
                     let update_result = self
                         .command_exec
-                        .update_board_label(None, name, color)
+                        .update_board_label(None, &name, &color)
                         .await;
-                    println!("{}", update_result.result_string.unwrap());
+                    self.print_result(&update_result);
                 }
             }
 
@@ -173,36 +289,37 @@ impl InteractiveCli {
         }
     }
 
-    async fn handle_board_command(&mut self, mut input_iter: std::str::SplitAsciiWhitespace<'_>) {
-        let available_commands = vec!["get-all", "select [<Name>]", "create-new <Name>", "help"];
-        match input_iter.next().unwrap_or("") {
+    async fn handle_board_command(&mut self, mut args: Arguments) {
+        let available_commands = BOARD_COMMANDS.to_vec();
+        match args.next().unwrap_or_default().as_str() {
             "help" => self.print_available_commands(&available_commands),
 
             "get-all" => {
                 let boards_result = self.command_exec.get_all_boards().await;
-                println!("{}", boards_result.result_string.unwrap());
-                match boards_result.result_code {
-                    CommandResultCode::Success => {
-                        let boards: Vec<Board> = boards_result.result.unwrap();
-                        println!("Board Names: ");
-                        for board in boards {
-                            println!("  - {}", board.name);
+                self.print_result(&boards_result);
+                if self.output_format == OutputFormat::Text {
+                    match boards_result.result_code {
+                        CommandResultCode::Success => {
+                            let boards: Vec<Board> = boards_result.result.unwrap();
+                            println!("Board Names: ");
+                            for board in boards {
+                                println!("  - {}", board.name);
+                            }
                         }
-                    }
 
-                    CommandResultCode::Failed => {
-                        println!("Command Failed.")
-                    }
-                };
+                        CommandResultCode::Failed => {
+                            println!("Command Failed.")
+                        }
+                    };
+                }
             }
 
             "select" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let mut board_name = remainder.join(" ");
+                let mut board_name = args.remainder();
                 if board_name.is_empty() {
                     let boards: Vec<Board> = self.command_exec.get_all_boards().await.result.unwrap_or(vec![]);
                     if boards.is_empty() {
-                        println!("Found no boards to select");
+                        self.print_error("Found no boards to select");
                         return;
                     } else {
                         let board_names: Vec<&str> = boards.iter().map(|board| board.name.as_str()).collect::<Vec<_>>();
@@ -211,7 +328,7 @@ impl InteractiveCli {
                     }
                 }
                 let board_result = self.command_exec.select_board(&board_name).await;
-                println!("{}", board_result.result_string.unwrap());
+                self.print_result(&board_result);
                 if let CommandResultCode::Success = board_result.result_code {
                     self.current_board.replace(board_result.result.unwrap());
                     self.current_list.take();
@@ -220,10 +337,94 @@ impl InteractiveCli {
             }
 
             "create-new" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let board_name = remainder.join(" ");
+                let board_name = args.remainder();
                 let board_result = self.command_exec.create_board(&board_name).await;
-                println!("{}", board_result.result_string.unwrap());
+                self.print_result(&board_result);
+            }
+
+            _ => {
+                self.print_invalid_command(None);
+                self.print_available_commands(&available_commands);
+            }
+        }
+    }
+
+    async fn handle_sync_command(&mut self, mut args: Arguments) {
+        let available_commands = SYNC_COMMANDS.to_vec();
+        match args.next().unwrap_or_default().as_str() {
+            "help" => self.print_available_commands(&available_commands),
+
+            "push" => {
+                let push_result = self.command_exec.push_outbox().await;
+                self.print_result(&push_result);
+            }
+
+            "board" => {
+                let name = args.remainder();
+                if name.is_empty() {
+                    self.print_error("Usage: sync board [--full] <name>");
+                    return;
+                }
+                let sync_result = self.command_exec.sync_board(&name, args.has_option("full")).await;
+                self.print_result(&sync_result);
+            }
+
+            "all" => {
+                let sync_result = self.command_exec.sync_all_boards(args.has_option("full")).await;
+                self.print_result(&sync_result);
+                if self.shows_details(&sync_result) {
+                    for snapshot in sync_result.result.unwrap() {
+                        println!("  {}: {}", snapshot.board.name, snapshot.describe());
+                    }
+                }
+            }
+
+            "purge" => {
+                let purge_result = self.command_exec.purge_tombstones().await;
+                self.print_result(&purge_result);
+            }
+
+            "conflicts" => match args.next() {
+                None => {
+                    let conflicts_result = self.command_exec.get_conflicts().await;
+                    self.print_result(&conflicts_result);
+                    if self.shows_details(&conflicts_result) {
+                        for (index, conflict) in conflicts_result.result.unwrap().iter().enumerate() {
+                            println!("  {}", output::describe_conflict(index + 1, conflict));
+                        }
+                    }
+                }
+
+                Some(number) => match (number.parse::<usize>(), args.next()) {
+                    (Ok(number), Some(side)) => {
+                        let fields: Vec<String> = args.collect();
+                        let resolve_result = self.command_exec.resolve_conflict(number, &side, &fields).await;
+                        self.print_result(&resolve_result);
+                    }
+                    _ => self.print_error("Usage: sync conflicts [<number> <local|trello> [<field>...]]"),
+                },
+            },
+
+            _ => {
+                self.print_invalid_command(None);
+                self.print_available_commands(&available_commands);
+            }
+        }
+    }
+
+    async fn handle_db_command(&mut self, mut args: Arguments) {
+        let available_commands = DB_COMMANDS.to_vec();
+        match args.next().unwrap_or_default().as_str() {
+            "help" => self.print_available_commands(&available_commands),
+
+            "migrate" => {
+                let migrate_result = self.command_exec.migrate_local_store(args.has_option("dry-run")).await;
+                self.print_result(&migrate_result);
+                if self.shows_details(&migrate_result) {
+                    for step in migrate_result.result.unwrap().steps {
+                        println!("  {}", step.describe());
+                    }
+                }
             }
 
             _ => {
@@ -233,36 +434,37 @@ impl InteractiveCli {
         }
     }
 
-    async fn handle_list_command(&mut self, mut input_iter: std::str::SplitAsciiWhitespace<'_>) {
-        let available_commands = vec!["get-all", "select [<Name>]", "create <Name>", "due-dates", "help"];
-        match input_iter.next().unwrap_or("") {
+    async fn handle_list_command(&mut self, mut args: Arguments) {
+        let available_commands = LIST_COMMANDS.to_vec();
+        match args.next().unwrap_or_default().as_str() {
             "help" => self.print_available_commands(&available_commands),
 
             "get-all" => {
                 let lists_result = self.command_exec.get_all_board_lists(None).await;
-                println!("{}", lists_result.result_string.unwrap());
-                match lists_result.result_code {
-                    CommandResultCode::Success => {
-                        let lists: Vec<BoardList> = lists_result.result.unwrap();
-                        println!("Lists: ");
-                        for list in lists {
-                            println!("  - {name}", name = list.name);
+                self.print_result(&lists_result);
+                if self.output_format == OutputFormat::Text {
+                    match lists_result.result_code {
+                        CommandResultCode::Success => {
+                            let lists: Vec<BoardList> = lists_result.result.unwrap();
+                            println!("Lists: ");
+                            for list in lists {
+                                println!("  - {name}", name = list.name);
+                            }
                         }
-                    }
 
-                    CommandResultCode::Failed => {
-                        println!("Command Failed. Do you have a board selected?");
+                        CommandResultCode::Failed => {
+                            println!("Command Failed. Do you have a board selected?");
+                        }
                     }
                 }
             }
 
             "select" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let mut list_name = remainder.join(" ");
+                let mut list_name = args.remainder();
                 if list_name.is_empty() {
                     let lists: Vec<BoardList> = self.command_exec.get_all_board_lists(None).await.result.unwrap_or(vec![]);
                     if lists.is_empty() {
-                        println!("Found no lists to select");
+                        self.print_error("Found no lists to select");
                         return;
                     } else {
                         let list_names: Vec<&str> = lists.iter().map(|list| list.name.as_str()).collect::<Vec<_>>();
@@ -271,7 +473,7 @@ impl InteractiveCli {
                     }
                 }
                 let list_result = self.command_exec.select_board_list(&list_name, None).await;
-                println!("{}", list_result.result_string.unwrap());
+                self.print_result(&list_result);
                 if let CommandResultCode::Success = list_result.result_code {
                     self.current_list.replace(list_result.result.unwrap());
                     self.current_card.take();
@@ -279,21 +481,20 @@ impl InteractiveCli {
             }
 
             "create" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let list_name = remainder.join(" ");
+                let list_name = args.remainder();
                 if list_name.is_empty() {
                     self.print_invalid_command(Some(String::from("You must provide a name")));
                     self.print_available_commands(&available_commands);
                 } else {
                     let list_result = self.command_exec.create_board_list(None, &list_name).await;
-                    println!("{}", list_result.result_string.unwrap());
+                    self.print_result(&list_result);
                 }
             }
 
             "due-dates" => {
                 let cards_result = self.command_exec.get_all_list_cards(None).await;
-                println!("{}", cards_result.result_string.unwrap());
-                if let CommandResultCode::Success = cards_result.result_code {
+                self.print_result(&cards_result);
+                if self.shows_details(&cards_result) {
                     println!("Card Due Dates:");
                     for card in cards_result.result.unwrap() {
                         let due_date: String = if card.due_date_instant_seconds == 0 {
@@ -314,31 +515,29 @@ impl InteractiveCli {
         }
     }
 
-    async fn handle_card_command(&mut self, mut input_iter: std::str::SplitAsciiWhitespace<'_>) {
-        let available_commands = vec!["create <Name>", "get-all", "select [<Name>]", "get-description", "edit-description <Text>", "move-to-list [<ListName>]", "get-labels", "add-label [<LabelName>]", "remove-label [<LabelName>]", "get-due-date", "set-due-date <yyyy-mm-dd hh:mm:ss>", "set-due-complete", "get-comments", "add-comment <Text>", "help"];
-        match input_iter.next().unwrap_or("") {
+    async fn handle_card_command(&mut self, mut args: Arguments) {
+        let available_commands = CARD_COMMANDS.to_vec();
+        match args.next().unwrap_or_default().as_str() {
             "help" => self.print_available_commands(&available_commands),
 
             "create" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let card_name = remainder.join(" ");
+                let card_name = args.remainder();
                 if card_name.is_empty() {
                     self.print_invalid_command(Some(String::from("You must provide a name")));
                     self.print_available_commands(&available_commands);
                 } else {
                     let card_result = self.command_exec.create_list_card(None, &card_name).await;
-                    println!("{}", card_result.result_string.unwrap());
+                    self.print_result(&card_result);
                 }
             }
 
 
             "select" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let mut card_name = remainder.join(" ");
+                let mut card_name = args.remainder();
                 if card_name.is_empty() {
                     let cards: Vec<Card> = self.command_exec.get_all_list_cards(None).await.result.unwrap_or(vec![]);
                     if cards.is_empty() {
-                        println!("Found no cards to select");
+                        self.print_error("Found no cards to select");
                         return;
                     } else {
                         let card_names: Vec<&str> = cards.iter().map(|card| card.name.as_str()).collect::<Vec<_>>();
@@ -347,18 +546,20 @@ impl InteractiveCli {
                     }
                 }
                 let card_result = self.command_exec.select_list_card(&card_name, None).await;
-                println!("{}", card_result.result_string.unwrap());
+                self.print_result(&card_result);
                 if let CommandResultCode::Success = card_result.result_code {
                     self.current_card.replace(card_result.result.unwrap());
-                    let description: String = self.current_card.clone().unwrap().description;
-                    println!("Card Description: \n{}", description);
+                    if self.output_format == OutputFormat::Text {
+                        let description: String = self.current_card.clone().unwrap().description;
+                        println!("Card Description: \n{}", description);
+                    }
                 }
             }
 
             "get-all" => {
                 let cards_result = self.command_exec.get_all_list_cards(None).await;
-                println!("{}", cards_result.result_string.unwrap());
-                if let CommandResultCode::Success = cards_result.result_code {
+                self.print_result(&cards_result);
+                if self.shows_details(&cards_result) {
                     println!("Cards:");
                     for card in cards_result.result.unwrap() {
                         println!("  - {}", card.name);
@@ -370,48 +571,52 @@ impl InteractiveCli {
 
             "get-description" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let card: Card = self.current_card.clone().unwrap();
-                    println!("Card Description:\n{}", card.description);
+                    if self.output_format == OutputFormat::Text {
+                        println!("Card Description:\n{}", card.description);
+                    } else {
+                        self.print_card(card);
+                    }
                 }
             }
 
             "edit-description" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let mut card: Card = self.current_card.clone().unwrap();
-                    let remainder: Vec<&str> = input_iter.collect();
-                    let description = remainder.join(" ");
+                    let mut description = args.remainder();
                     if description.is_empty() {
-                        self.print_invalid_command(Some(String::from("You must provide a description")));
-                        self.print_available_commands(&available_commands);
-                    } else {
-                        card.description = description;
-                        let card_result = self.command_exec.update_card(&card).await;
-                        println!("{}", card_result.result_string.unwrap());
-                        if let CommandResultCode::Success = card_result.result_code {
-                            self.current_card.replace(card_result.result.unwrap());
+                        match self.text_from_editor(&card.description) {
+                            Some(text) => description = text,
+                            None => return,
                         }
                     }
+
+                    card.description = description;
+                    let card_result = self.command_exec.update_card(&card).await;
+                    self.print_result(&card_result);
+                    if let CommandResultCode::Success = card_result.result_code {
+                        self.current_card.replace(card_result.result.unwrap());
+                    }
                 }
             }
 
             "move-to-list" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let card: Card = self.current_card.clone().unwrap();
-                    let remainder: Vec<&str> = input_iter.collect();
-                    let mut list_name = remainder.join(" ");
+                    let mut list_name = args.remainder();
                     if list_name.is_empty() {
                         let lists: Vec<BoardList> = self.command_exec.get_all_board_lists(None).await.result.unwrap_or(vec![]);
                         if lists.is_empty() {
-                            println!("Found no lists to move to");
+                            self.print_error("Found no lists to move to");
                             return;
                         } else {
                             let list_names: Vec<&str> = lists.iter().map(|list| list.name.as_str()).collect::<Vec<_>>();
@@ -420,7 +625,7 @@ impl InteractiveCli {
                         }
                     }
                     let card_result = self.command_exec.move_card_to_list(card, &list_name).await;
-                    println!("{}", card_result.result_string.unwrap());
+                    self.print_result(&card_result);
                     if let CommandResultCode::Success = card_result.result_code {
                         self.current_card.replace(card_result.result.unwrap());
                         let list_result = self.command_exec.select_board_list(&list_name, None).await;
@@ -432,13 +637,13 @@ impl InteractiveCli {
 
             "get-labels" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let card = self.current_card.clone().unwrap();
                     let labels_result = self.command_exec.get_card_labels(&card).await;
-                    println!("{}", labels_result.result_string.unwrap());
-                    if let CommandResultCode::Success = labels_result.result_code {
+                    self.print_result(&labels_result);
+                    if self.shows_details(&labels_result) {
                         let labels: Vec<CardLabel> = labels_result.result.unwrap();
                         for label in labels {
                             println!("  {name}: {color}", name = label.name, color = label.color);
@@ -450,16 +655,15 @@ impl InteractiveCli {
 
             "add-label" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let card: Card = self.current_card.clone().unwrap();
-                    let remainder: Vec<&str> = input_iter.collect();
-                    let mut label_name = remainder.join(" ");
+                    let mut label_name = args.remainder();
                     if label_name.is_empty() {
                         let labels: Vec<CardLabel> = self.command_exec.get_all_board_labels(None).await.result.unwrap_or(vec![]);
                         if labels.is_empty() {
-                            println!("Found no labels to add");
+                            self.print_error("Found no labels to add");
                             return;
                         } else {
                             let label_names: Vec<&str> = labels.iter().map(|label| label.name.as_str()).collect::<Vec<_>>();
@@ -468,7 +672,7 @@ impl InteractiveCli {
                         }
                     }
                     let card_result = self.command_exec.add_card_label(card, &label_name).await;
-                    println!("{}", card_result.result_string.unwrap());
+                    self.print_result(&card_result);
                     if let CommandResultCode::Success = card_result.result_code {
                         self.current_card.replace(card_result.result.unwrap());
                     }
@@ -478,16 +682,15 @@ impl InteractiveCli {
 
             "remove-label" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let card: Card = self.current_card.clone().unwrap();
-                    let remainder: Vec<&str> = input_iter.collect();
-                    let mut label_name = remainder.join(" ");
+                    let mut label_name = args.remainder();
                     if label_name.is_empty() {
                         let labels: Vec<CardLabel> = self.command_exec.get_card_labels(&card).await.result.unwrap_or(vec![]);
                         if labels.is_empty() {
-                            println!("Found no labels to remove");
+                            self.print_error("Found no labels to remove");
                             return;
                         } else {
                             let label_names: Vec<&str> = labels.iter().map(|label| label.name.as_str()).collect::<Vec<_>>();
@@ -496,7 +699,7 @@ impl InteractiveCli {
                         }
                     }
                     let card_result = self.command_exec.remove_card_label(card, &label_name).await;
-                    println!("{}", card_result.result_string.unwrap());
+                    self.print_result(&card_result);
                     if let CommandResultCode::Success = card_result.result_code {
                         self.current_card.replace(card_result.result.unwrap());
                     }
@@ -506,7 +709,7 @@ impl InteractiveCli {
 
             "get-due-date" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let card: Card = self.current_card.clone().unwrap();
@@ -516,18 +719,21 @@ impl InteractiveCli {
                         let datetime = Local.timestamp(card.due_date_instant_seconds, 0);
                         format!("{}", datetime.to_rfc2822())
                     };
-                    println!("Card Due:\n  {}", due_date);
+                    if self.output_format == OutputFormat::Text {
+                        println!("Card Due:\n  {}", due_date);
+                    } else {
+                        self.print_card(card);
+                    }
                 }
             }
 
             "set-due-date" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let mut card: Card = self.current_card.clone().unwrap();
-                    let remainder: Vec<&str> = input_iter.collect();
-                    let due_string = remainder.join(" ");
+                    let due_string = args.remainder();
                     if due_string.is_empty() {
                         self.print_invalid_command(Some(String::from("You must provide a new due date")));
                         self.print_available_commands(&available_commands);
@@ -536,12 +742,12 @@ impl InteractiveCli {
                         if due_datetime.is_some() {
                             card.due_date_instant_seconds = due_datetime.unwrap().timestamp();
                             let card_result = self.command_exec.update_card(&card).await;
-                            println!("{}", card_result.result_string.unwrap());
+                            self.print_result(&card_result);
                             if let CommandResultCode::Success = card_result.result_code {
                                 self.current_card.replace(card_result.result.unwrap());
                             }
                         } else {
-                            println!("Unable to parse the due date given");
+                            self.print_error("Unable to parse the due date given");
                         }
                     }
                 }
@@ -549,13 +755,13 @@ impl InteractiveCli {
 
             "set-due-complete" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let mut card: Card = self.current_card.clone().unwrap();
                     card.due_complete = true;
                     let card_result = self.command_exec.update_card(&card).await;
-                    println!("{}", card_result.result_string.unwrap());
+                    self.print_result(&card_result);
                     if let CommandResultCode::Success = card_result.result_code {
                         self.current_card.replace(card_result.result.unwrap());
                     }
@@ -564,13 +770,13 @@ impl InteractiveCli {
 
             "get-comments" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let card = self.current_card.clone().unwrap();
                     let comments_result = self.command_exec.get_card_comments(Some(card)).await;
-                    println!("{}", comments_result.result_string.unwrap());
-                    if let CommandResultCode::Success = comments_result.result_code {
+                    self.print_result(&comments_result);
+                    if self.shows_details(&comments_result) {
                         let comments: Vec<CardComment> = comments_result.result.unwrap();
                         for comment in comments {
                             let comment_date: String = if comment.comment_time_instant_seconds == 0 {
@@ -587,19 +793,20 @@ impl InteractiveCli {
 
             "add-comment" => {
                 if self.current_card.is_none() {
-                    println!("No card has been selected");
+                    self.print_error("No card has been selected");
                     self.print_available_commands(&available_commands);
                 } else {
                     let card: Card = self.current_card.clone().unwrap();
-                    let remainder: Vec<&str> = input_iter.collect();
-                    let text = remainder.join(" ");
+                    let mut text = args.remainder();
                     if text.is_empty() {
-                        self.print_invalid_command(Some(String::from("You must provide comment text")));
-                        self.print_available_commands(&available_commands);
-                    } else {
-                        let comment_result = self.command_exec.add_card_comment(Some(card), &text).await;
-                        println!("{}", comment_result.result_string.unwrap());
+                        match self.text_from_editor("") {
+                            Some(edited) => text = edited,
+                            None => return,
+                        }
                     }
+
+                    let comment_result = self.command_exec.add_card_comment(Some(card), &text).await;
+                    self.print_result(&comment_result);
                 }
             }
 
@@ -612,36 +819,37 @@ impl InteractiveCli {
 
     }
 
-    async fn handle_checklist_command(&mut self, mut input_iter: std::str::SplitAsciiWhitespace<'_>) {
-        let available_commands = vec!["get-all", "select [<Name>]", "create <Name>", "get-tasks", "add-task <Name>", "complete-task [<Name>]", "help"];
-        match input_iter.next().unwrap_or("") {
+    async fn handle_checklist_command(&mut self, mut args: Arguments) {
+        let available_commands = CHECKLIST_COMMANDS.to_vec();
+        match args.next().unwrap_or_default().as_str() {
             "help" => self.print_available_commands(&available_commands),
 
             "get-all" => {
                 let checklists_results = self.command_exec.get_card_checklists(None).await;
-                println!("{}", checklists_results.result_string.unwrap());
-                match checklists_results.result_code {
-                    CommandResultCode::Success => {
-                        let checklists: Vec<CardChecklist> = checklists_results.result.unwrap();
-                        println!("Checklists: ");
-                        for checklist in checklists {
-                            println!("  - {name}", name = checklist.name);
+                self.print_result(&checklists_results);
+                if self.output_format == OutputFormat::Text {
+                    match checklists_results.result_code {
+                        CommandResultCode::Success => {
+                            let checklists: Vec<CardChecklist> = checklists_results.result.unwrap();
+                            println!("Checklists: ");
+                            for checklist in checklists {
+                                println!("  - {name}", name = checklist.name);
+                            }
                         }
-                    }
 
-                    CommandResultCode::Failed => {
-                        println!("Command Failed. Do you have a card selected?");
+                        CommandResultCode::Failed => {
+                            println!("Command Failed. Do you have a card selected?");
+                        }
                     }
                 }
             }
 
             "select" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let mut checklist_name = remainder.join(" ");
+                let mut checklist_name = args.remainder();
                 if checklist_name.is_empty() {
                     let checklists: Vec<CardChecklist> = self.command_exec.get_card_checklists(None).await.result.unwrap_or(vec![]);
                     if checklists.is_empty() {
-                        println!("Found no checklists to select");
+                        self.print_error("Found no checklists to select");
                         return;
                     } else {
                         let checklist_names: Vec<&str> = checklists.iter().map(|checklist| checklist.name.as_str()).collect::<Vec<_>>();
@@ -650,54 +858,54 @@ impl InteractiveCli {
                     }
                 }
                 let checklist_result = self.command_exec.select_card_checklist(None, &checklist_name).await;
-                println!("{}", checklist_result.result_string.unwrap());
+                self.print_result(&checklist_result);
                 if let CommandResultCode::Success = checklist_result.result_code {
                     self.current_checklist.replace(checklist_result.result.unwrap());
                 }
             }
 
             "create" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let checklist_name = remainder.join(" ");
+                let checklist_name = args.remainder();
                 if checklist_name.is_empty() {
                     self.print_invalid_command(Some(String::from("You must provide a name")));
                     self.print_available_commands(&available_commands);
                 } else {
                     let checklist_result = self.command_exec.create_card_checklists(None, &checklist_name).await;
-                    println!("{}", checklist_result.result_string.unwrap());
+                    self.print_result(&checklist_result);
                 }
             }
 
             "get-tasks" => {
                 let tasks_results = self.command_exec.get_checklist_tasks(None).await;
-                println!("{}", tasks_results.result_string.unwrap());
-                match tasks_results.result_code {
-                    CommandResultCode::Success => {
-                        let tasks: Vec<CardChecklistTask> = tasks_results.result.unwrap();
-                        println!("Tasks: ");
-                        for task in tasks {
-                            let complete = if task.is_complete {
-                                "complete"
-                            } else {
-                                "incomplete"
-                            };
-                            println!("  - [{complete}] {name}", complete = complete, name = task.name);
+                self.print_result(&tasks_results);
+                if self.output_format == OutputFormat::Text {
+                    match tasks_results.result_code {
+                        CommandResultCode::Success => {
+                            let tasks: Vec<CardChecklistTask> = tasks_results.result.unwrap();
+                            println!("Tasks: ");
+                            for task in tasks {
+                                let complete = if task.is_complete {
+                                    "complete"
+                                } else {
+                                    "incomplete"
+                                };
+                                println!("  - [{complete}] {name}", complete = complete, name = task.name);
+                            }
                         }
-                    }
 
-                    CommandResultCode::Failed => {
-                        println!("Command Failed. Do you have a checklist selected?");
+                        CommandResultCode::Failed => {
+                            println!("Command Failed. Do you have a checklist selected?");
+                        }
                     }
                 }
             }
 
             "complete-task" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let mut task_name = remainder.join(" ");
+                let mut task_name = args.remainder();
                 if task_name.is_empty() {
                     let tasks: Vec<CardChecklistTask> = self.command_exec.get_checklist_tasks(None).await.result.unwrap_or(vec![]);
                     if tasks.is_empty() {
-                        println!("Found no tasks to complete");
+                        self.print_error("Found no tasks to complete");
                         return;
                     } else {
                         let task_names: Vec<&str> = tasks.iter().map(|task| task.name.as_str()).collect::<Vec<_>>();
@@ -708,26 +916,33 @@ impl InteractiveCli {
                 let tasks_result = self.command_exec.get_checklist_tasks(None).await;
                 if let CommandResultCode::Success = tasks_result.result_code {
                     let tasks: Vec<CardChecklistTask> = tasks_result.result.unwrap();
+                    let mut found_task = false;
                     for mut task in tasks {
                         if task.name.eq_ignore_ascii_case(&task_name) {
                             task.is_complete = true;
                             let task_result = self.command_exec.update_checklist_task(None, task).await;
-                            println!("{}", task_result.result_string.unwrap());
+                            self.print_result(&task_result);
+                            found_task = true;
                             break;
                         }
                     }
+
+                    if !found_task {
+                        self.print_error("Could not find a task with the given name");
+                    }
+                } else {
+                    self.print_result(&tasks_result);
                 }
             }
 
             "add-task" => {
-                let remainder: Vec<&str> = input_iter.collect();
-                let task_name = remainder.join(" ");
+                let task_name = args.remainder();
                 if task_name.is_empty() {
                     self.print_invalid_command(Some(String::from("You must provide a name")));
                     self.print_available_commands(&available_commands);
                 } else {
                     let task_result = self.command_exec.create_checklist_task(None, &task_name).await;
-                    println!("{}", task_result.result_string.unwrap());
+                    self.print_result(&task_result);
                 }
             }
 
@@ -737,67 +952,487 @@ impl InteractiveCli {
             }
         }
     }
-}
 
-pub async fn run(config: Option<serde_json::Value>) {
-    let command_exec = CommandExecutor::new(config).await;
-    let mut cli = InteractiveCli {
-        command_exec: command_exec,
-        current_board: None,
-        current_list: None,
-        current_card: None,
-        current_checklist: None,
-    };
+    async fn new(config: Option<serde_json::Value>, output_format: OutputFormat, is_script: bool) -> Result<InteractiveCli, Box<dyn std::error::Error>> {
+        let command_exec = CommandExecutor::new(config).await?;
+        Ok(InteractiveCli {
+            command_exec: command_exec,
+            output_format: output_format,
+            is_script: is_script,
+            command_failed: false,
+            current_board: None,
+            current_list: None,
+            current_card: None,
+            current_checklist: None,
+            completion_selection: None,
+        })
+    }
 
-    let available_commands = vec!["board", "label", "list", "card", "checklist", "exit", "help"];
+    fn current_path(&self) -> Vec<String> {
+        let names = [
+            self.current_board.as_ref().map(|board| board.name.clone()),
+            self.current_list.as_ref().map(|list| list.name.clone()),
+            self.current_card.as_ref().map(|card| card.name.clone()),
+            self.current_checklist.as_ref().map(|checklist| checklist.name.clone()),
+        ];
+        names.iter().map_while(|name| name.clone()).collect()
+    }
 
-    loop {
-        cli.print_prompt();
+    /**
+     * Goes up to `depth` levels below the root, dropping the deeper selections here and in the data repository.
+     */
+    fn truncate_path(&mut self, depth: usize) {
+        if depth < 1 {
+            self.current_board.take();
+        }
+        if depth < 2 {
+            self.current_list.take();
+        }
+        if depth < 3 {
+            self.current_card.take();
+        }
+        if depth < 4 {
+            self.current_checklist.take();
+        }
+        self.command_exec.deselect(depth < 1, depth < 2, depth < 3, depth < 4);
+    }
 
-        let mut input = String::new();
-        let read_result = io::stdin().read_line(&mut input);
-        if read_result.is_err() {
-            println!("Error while reading input: {}", read_result.unwrap_err());
-            break;
+    /**
+     * Goes back to a selection made before, here and in the data repository.
+     */
+    fn restore_selection(&mut self, board: Option<Board>, list: Option<BoardList>, card: Option<Card>, checklist: Option<CardChecklist>) {
+        self.current_board = board.clone();
+        self.current_list = list.clone();
+        self.current_card = card.clone();
+        self.current_checklist = checklist.clone();
+        self.command_exec.restore_selection(board, list, card, checklist);
+    }
+
+    /**
+     * Selects the child `name` of the current node. Returns whether it was found.
+     */
+    async fn enter(&mut self, name: &str) -> bool {
+        match self.current_path().len() {
+            0 => {
+                let board_result = self.command_exec.select_board(name).await;
+                self.current_board = board_result.result;
+                self.current_board.is_some()
+            }
+
+            1 => {
+                let list_result = self.command_exec.select_board_list(name, None).await;
+                self.current_list = list_result.result;
+                self.current_list.is_some()
+            }
+
+            2 => {
+                let card_result = self.command_exec.select_list_card(name, None).await;
+                self.current_card = card_result.result;
+                self.current_card.is_some()
+            }
+
+            3 => {
+                let checklist_result = self.command_exec.select_card_checklist(None, name).await;
+                self.current_checklist = checklist_result.result;
+                self.current_checklist.is_some()
+            }
+
+            _ => false,
         }
+    }
 
-        if input.trim_end().eq_ignore_ascii_case("exit") {
-            break;
+    async fn change_directory(&mut self, mut args: Arguments) {
+        let path = args.remainder();
+        let current = self.current_path();
+        let target = match navigation::resolve_path(&current, if path.is_empty() { "/" } else { &path }) {
+            Ok(target) => target,
+            Err(why) => {
+                self.print_error(&why);
+                return;
+            }
+        };
+
+        // Nothing changes unless every component of the path can be entered
+        let board = self.current_board.clone();
+        let list = self.current_list.clone();
+        let card = self.current_card.clone();
+        let checklist = self.current_checklist.clone();
+
+        let common_depth = navigation::common_depth(&current, &target);
+        if common_depth < current.len() {
+            self.truncate_path(common_depth);
         }
 
-        if input.is_empty() {
-            continue;
+        for name in target.iter().skip(common_depth) {
+            if !self.enter(name).await {
+                let parent = navigation::format_path(&self.current_path());
+                self.restore_selection(board, list, card, checklist);
+                self.print_error(&format!("No such board, list, card or checklist '{}' in {}", name, parent));
+                return;
+            }
+        }
+
+        if self.output_format != OutputFormat::Text {
+            self.print_working_directory();
         }
+    }
 
-        let mut input_iter = input.split_ascii_whitespace();
-        match input_iter.next().unwrap_or("") {
-            "" => continue,
-            "help" => cli.print_available_commands(&available_commands),
+    fn print_working_directory(&mut self) {
+        let path = navigation::format_path(&self.current_path());
+        self.print_result(&CommandResult {
+            result_code: CommandResultCode::Success,
+            result: Some(path.clone()),
+            result_string: Some(path),
+            last_synced_instant_seconds: None,
+        });
+    }
+
+    fn print_listing<T: Serialize>(&mut self, result: CommandResult<Vec<T>>, format_item: fn(&T) -> String) {
+        if self.output_format != OutputFormat::Text || !self.shows_details(&result) {
+            self.print_result(&result);
+            return;
+        }
+
+        for item in result.result.unwrap_or_default() {
+            println!("{}", format_item(&item));
+        }
+    }
+
+    async fn list_directory(&mut self) {
+        match self.current_path().len() {
+            0 => {
+                let boards_result = self.command_exec.get_all_boards().await;
+                self.print_listing(boards_result, |board| board.name.clone());
+            }
+
+            1 => {
+                let lists_result = self.command_exec.get_all_board_lists(None).await;
+                self.print_listing(lists_result, |list| list.name.clone());
+            }
+
+            2 => {
+                let cards_result = self.command_exec.get_all_list_cards(None).await;
+                self.print_listing(cards_result, |card| card.name.clone());
+            }
+
+            3 => {
+                let checklists_result = self.command_exec.get_card_checklists(None).await;
+                self.print_listing(checklists_result, |checklist| checklist.name.clone());
+            }
+
+            _ => {
+                let tasks_result = self.command_exec.get_checklist_tasks(None).await;
+                self.print_listing(tasks_result, |task| {
+                    format!("[{}] {}", if task.is_complete { "x" } else { " " }, task.name)
+                });
+            }
+        }
+    }
+
+    /**
+     * Names offered by tab completion. Whatever the data repository has cached is used as is;
+     * names belonging to the current selection that are not cached yet are fetched once per selection,
+     * since that can wait on the network. Until the selection changes, names a command dropped from
+     * the cache since are offered as they were.
+     */
+    async fn completion_names(&mut self) -> CachedNames {
+        let path = self.current_path();
+        let names = self.command_exec.get_cached_names();
+        if let Some((completed_path, offered)) = self.completion_selection.take() {
+            if completed_path == path {
+                let names = CachedNames {
+                    boards: names.boards.or(offered.boards),
+                    lists: names.lists.or(offered.lists),
+                    cards: names.cards.or(offered.cards),
+                    checklists: names.checklists.or(offered.checklists),
+                    labels: names.labels.or(offered.labels),
+                    tasks: names.tasks.or(offered.tasks),
+                };
+                self.completion_selection = Some((path, names.clone()));
+                return names;
+            }
+        }
+
+        if names.boards.is_none() {
+            self.command_exec.get_all_boards().await;
+        }
+
+        if self.current_board.is_some() {
+            if names.lists.is_none() {
+                self.command_exec.get_all_board_lists(None).await;
+            }
+            if names.labels.is_none() {
+                self.command_exec.get_all_board_labels(None).await;
+            }
+        }
+
+        if self.current_list.is_some() && names.cards.is_none() {
+            self.command_exec.get_all_list_cards(None).await;
+        }
+
+        if self.current_card.is_some() && names.checklists.is_none() {
+            self.command_exec.get_card_checklists(None).await;
+        }
+
+        if self.current_checklist.is_some() && names.tasks.is_none() {
+            self.command_exec.get_checklist_tasks(None).await;
+        }
+
+        let names = self.command_exec.get_cached_names();
+        self.completion_selection = Some((path, names.clone()));
+        names
+    }
+
+    async fn execute_line(&mut self, input: &str) -> CommandResultCode {
+        let available_commands = SHELL_COMMANDS.to_vec();
+        self.command_failed = false;
+
+        let mut args = match Arguments::parse(input) {
+            Ok(args) => args,
+            Err(why) => {
+                self.print_invalid_command(Some(why));
+                return CommandResultCode::Failed;
+            }
+        };
+
+        match args.next().unwrap_or_default().as_str() {
+            "" => {}
+            "help" => self.print_available_commands(&available_commands),
 
             "board" => {
-                cli.handle_board_command(input_iter).await;
+                self.handle_board_command(args).await;
             }
 
             "label" => {
-                cli.handle_label_command(input_iter).await;
+                self.handle_label_command(args).await;
             }
 
             "list" => {
-                cli.handle_list_command(input_iter).await;
+                self.handle_list_command(args).await;
             }
 
             "card" => {
-                cli.handle_card_command(input_iter).await;
+                self.handle_card_command(args).await;
             }
 
             "checklist" => {
-                cli.handle_checklist_command(input_iter).await;
+                self.handle_checklist_command(args).await;
+            }
+
+            "sync" => {
+                self.handle_sync_command(args).await;
+            }
+
+            "db" => {
+                self.handle_db_command(args).await;
             }
 
+            "cd" => self.change_directory(args).await,
+            "ls" => self.list_directory().await,
+            "pwd" => self.print_working_directory(),
+
             _ => {
-                cli.print_invalid_command(None);
-                cli.print_available_commands(&available_commands);
+                self.print_invalid_command(None);
+                self.print_available_commands(&available_commands);
+            }
+        }
+
+        if self.command_failed {
+            CommandResultCode::Failed
+        } else {
+            CommandResultCode::Success
+        }
+    }
+}
+
+fn editor_config(config: &Option<serde_json::Value>) -> Config {
+    let edit_mode = config
+        .as_ref()
+        .and_then(|config_object| config_object.get("shell"))
+        .and_then(|shell| shell.get("edit_mode"))
+        .and_then(|edit_mode| edit_mode.as_str())
+        .unwrap_or("emacs");
+
+    let edit_mode = if edit_mode.eq_ignore_ascii_case("vi") {
+        EditMode::Vi
+    } else {
+        EditMode::Emacs
+    };
+
+    Config::builder()
+        .edit_mode(edit_mode)
+        .history_ignore_dups(true)
+        .history_ignore_space(true)
+        .max_history_size(HISTORY_SIZE)
+        .completion_type(CompletionType::List)
+        .build()
+}
+
+pub async fn run(config: Option<serde_json::Value>, output_format: OutputFormat, stop_on_error: bool, history_path: PathBuf) -> CommandResultCode {
+    if !io::stdin().is_terminal() {
+        let stdin = io::stdin();
+        return run_script(config, output_format, stdin.lock(), stop_on_error).await;
+    }
+
+    let mut editor = Editor::<ShellHelper>::with_config(editor_config(&config));
+    editor.set_helper(Some(ShellHelper::default()));
+    if history_path.is_file() {
+        if let Err(why) = editor.load_history(&history_path) {
+            println!("Failed to load command history: {}", why);
+        }
+    }
+
+    let mut cli = match InteractiveCli::new(config, output_format, false).await {
+        Ok(cli) => cli,
+        Err(why) => {
+            eprintln!("Failed to set up the data stores: {}", why);
+            return CommandResultCode::Failed;
+        }
+    };
+
+    loop {
+        let names = cli.completion_names().await;
+        if let Some(helper) = editor.helper_mut() {
+            helper.set_names(names, cli.current_path().len());
+        }
+
+        println!();
+        let input = match editor.readline(&cli.prompt()) {
+            Ok(input) => input,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(why) => {
+                println!("Error while reading input: {}", why);
+                break;
+            }
+        };
+
+        if input.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(input.as_str());
+
+        if input.trim_end().eq_ignore_ascii_case("exit") {
+            break;
+        }
+
+        cli.execute_line(&input).await;
+    }
+
+    if let Err(why) = editor.save_history(&history_path) {
+        println!("Failed to save command history: {}", why);
+    }
+
+    CommandResultCode::Success
+}
+
+/**
+ * Runs shell commands read line by line from `script`, carrying the board/list/card/checklist
+ * selection from one line to the next. Blank lines and lines starting with `#` are skipped.
+ * Failed lines are reported on stderr with their line number; when `stop_on_error` is set the
+ * script is aborted at the first failure. Returns `Failed` if any line failed.
+ */
+pub async fn run_script<R: BufRead>(config: Option<serde_json::Value>, output_format: OutputFormat, script: R, stop_on_error: bool) -> CommandResultCode {
+    let mut cli = match InteractiveCli::new(config, output_format, true).await {
+        Ok(cli) => cli,
+        Err(why) => {
+            eprintln!("Failed to set up the data stores: {}", why);
+            return CommandResultCode::Failed;
+        }
+    };
+    execute_script(&mut cli, script, stop_on_error, &mut io::stderr()).await
+}
+
+/**
+ * Runs each script line in `cli`, so selections carry over from one line to the next.
+ * Line failures are reported to `errors`.
+ */
+async fn execute_script<R: BufRead, W: Write>(cli: &mut InteractiveCli, script: R, stop_on_error: bool, errors: &mut W) -> CommandResultCode {
+    let mut script_failed = false;
+
+    for (index, line_result) in script.lines().enumerate() {
+        let line_number = index + 1;
+        let line = match line_result {
+            Ok(line) => line,
+            Err(why) => {
+                let _ = writeln!(errors, "Error while reading line {}: {}", line_number, why);
+                return CommandResultCode::Failed;
+            }
+        };
+
+        let command = line.trim();
+        if command.is_empty() || command.starts_with('#') {
+            continue;
+        }
+
+        if command.eq_ignore_ascii_case("exit") {
+            break;
+        }
+
+        if let CommandResultCode::Failed = cli.execute_line(command).await {
+            script_failed = true;
+            let _ = writeln!(errors, "Line {} failed: {}", line_number, command);
+            if stop_on_error {
+                break;
             }
         }
     }
+
+    if script_failed {
+        CommandResultCode::Failed
+    } else {
+        CommandResultCode::Success
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn cd_to_a_missing_path_keeps_the_current_one() {
+        let config = Some(json!({ "stores": { "remote": "memory", "local": "memory" } }));
+        let mut cli = InteractiveCli::new(config, OutputFormat::Json, true).await.unwrap();
+        for line in ["board create-new Eng", "board create-new Ops", "cd /Ops", "list create Todo", "cd Todo"] {
+            cli.execute_line(line).await;
+        }
+        assert_eq!(cli.current_path(), vec!["Ops", "Todo"]);
+
+        cli.execute_line("cd /Eng/Missing").await;
+        assert!(cli.command_failed);
+        assert_eq!(cli.current_path(), vec!["Ops", "Todo"]);
+        let lists = cli.command_exec.get_all_board_lists(None).await.result.unwrap();
+        assert_eq!(lists[0].name, "Todo");
+    }
+
+    #[tokio::test]
+    async fn scripts_report_failed_lines_and_keep_the_selection() {
+        let config = Some(json!({ "stores": { "remote": "memory", "local": "memory" } }));
+        let script = "# set up the board\nboard create-new Eng\ncd /Eng\nlist create Todo\ncd Missing\ncd Todo\n";
+
+        let mut cli = InteractiveCli::new(config.clone(), OutputFormat::Json, true).await.unwrap();
+        let mut errors = vec![];
+        let result = execute_script(&mut cli, io::Cursor::new(script), false, &mut errors).await;
+        assert!(matches!(result, CommandResultCode::Failed));
+        assert_eq!(String::from_utf8(errors).unwrap(), "Line 5 failed: cd Missing\n");
+        assert_eq!(cli.current_path(), vec!["Eng", "Todo"]);
+
+        let mut cli = InteractiveCli::new(config, OutputFormat::Json, true).await.unwrap();
+        let mut errors = vec![];
+        let result = execute_script(&mut cli, io::Cursor::new(script), true, &mut errors).await;
+        assert!(matches!(result, CommandResultCode::Failed));
+        assert_eq!(String::from_utf8(errors).unwrap(), "Line 5 failed: cd Missing\n");
+        assert_eq!(cli.current_path(), vec!["Eng"]);
+    }
+
+    #[tokio::test]
+    async fn script_exit_code_follows_its_lines() {
+        let config = Some(json!({ "stores": { "remote": "memory", "local": "memory" } }));
+        let passing = io::Cursor::new("board create-new Eng\ncd /Eng\nexit\ncd Missing\n");
+        assert!(matches!(run_script(config.clone(), OutputFormat::Json, passing, true).await, CommandResultCode::Success));
+
+        let failing = io::Cursor::new("board create-new Eng\ncd /Ops\n");
+        assert!(matches!(run_script(config, OutputFormat::Json, failing, true).await, CommandResultCode::Failed));
+    }
 }
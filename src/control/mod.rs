@@ -1,14 +1,43 @@
+pub mod command_line;
+pub mod completion;
+pub mod editor;
 pub mod interactive_cli;
+pub mod navigation;
+pub mod output;
+pub mod tokenizer;
+pub mod tui;
 
+use serde::Serialize;
+
+#[derive(Serialize)]
 pub enum CommandResultCode {
     Success,
     Failed,
 }
 
+impl CommandResultCode {
+    /**
+     * The process exit status for this result: 0 on success, 1 on failure.
+     */
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandResultCode::Success => 0,
+            CommandResultCode::Failed => 1,
+        }
+    }
+}
+
+#[derive(Serialize)]
 pub struct CommandResult<T> {
     pub result_code: CommandResultCode,
     pub result: Option<T>,
     pub result_string: Option<String>,
+    /**
+     * Set when Trello could not be reached and the result was read from the local store instead:
+     * when that copy was last synced from Trello, or 0 if it never was.
+     */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_synced_instant_seconds: Option<i64>,
 }
 
 pub mod command_executor {
@@ -21,9 +50,193 @@ pub mod command_executor {
     }
 
     impl CommandExecutor {
-        pub async fn new(config: Option<serde_json::Value>) -> CommandExecutor {
-            let bs = BoardService::new(config).await;
-            CommandExecutor { board_service: bs }
+        /**
+         * Fails when the stores in the config cannot be set up, e.g. an unknown store name or an unreachable MongoDB.
+         */
+        pub async fn new(config: Option<serde_json::Value>) -> Result<CommandExecutor, Box<dyn std::error::Error>> {
+            let bs = BoardService::new(config).await?;
+            Ok(CommandExecutor { board_service: bs })
+        }
+
+        pub fn deselect(&mut self, board: bool, list: bool, card: bool, checklist: bool) {
+            self.board_service.deselect(board, list, card, checklist)
+        }
+
+        pub fn restore_selection(&mut self, board: Option<Board>, list: Option<BoardList>, card: Option<Card>, checklist: Option<CardChecklist>) {
+            self.board_service.restore_selection(board, list, card, checklist)
+        }
+
+        pub fn get_cached_names(&self) -> CachedNames {
+            self.board_service.get_cached_names()
+        }
+
+        pub async fn push_outbox(&mut self) -> CommandResult<usize> {
+            match self.board_service.push_outbox().await {
+                Ok(pushed) => CommandResult {
+                    result_code: CommandResultCode::Success,
+                    result: Some(pushed),
+                    result_string: Some(self.with_conflict_note(format!("Pushed {} change(s) to Trello", pushed))),
+                    last_synced_instant_seconds: None,
+                },
+
+                Err(why) => CommandResult {
+                    result_code: CommandResultCode::Failed,
+                    result: None,
+                    result_string: Some(self.with_conflict_note(why.to_string())),
+                    last_synced_instant_seconds: None,
+                },
+            }
+        }
+
+        /**
+         * Tells the user when the last write ran into changes made on Trello to the same fields.
+         */
+        fn with_conflict_note(&mut self, result_string: String) -> String {
+            match self.board_service.take_recorded_conflicts() {
+                0 => result_string,
+                conflicts => format!("{}. {} field(s) were also changed on Trello and kept Trello's value, see `sync conflicts`", result_string, conflicts),
+            }
+        }
+
+        pub async fn get_conflicts(&mut self) -> CommandResult<Vec<SyncConflict>> {
+            match self.board_service.get_conflicts().await {
+                Ok(conflicts) => CommandResult {
+                    result_code: CommandResultCode::Success,
+                    result_string: Some(format!("Retrieved {} conflict(s)", conflicts.len())),
+                    result: Some(conflicts),
+                    last_synced_instant_seconds: None,
+                },
+
+                Err(why) => CommandResult {
+                    result_code: CommandResultCode::Failed,
+                    result: None,
+                    result_string: Some(why.to_string()),
+                    last_synced_instant_seconds: None,
+                },
+            }
+        }
+
+        /**
+         * Picks a side, `local` or `trello`, for `fields` of the conflict with the given number (as listed, starting at 1).
+         * Without fields the side is picked for all of them.
+         */
+        pub async fn resolve_conflict(&mut self, number: usize, side: &str, fields: &[String]) -> CommandResult<SyncConflict> {
+            let use_local = match side.to_ascii_lowercase().as_str() {
+                "local" => true,
+                "trello" => false,
+                _ => {
+                    return CommandResult {
+                        result_code: CommandResultCode::Failed,
+                        result: None,
+                        result_string: Some(format!("Unknown side '{}'. Pick either local or trello", side)),
+                        last_synced_instant_seconds: None,
+                    }
+                }
+            };
+
+            match self.board_service.resolve_conflict(number.saturating_sub(1), use_local, fields).await {
+                Ok(conflict) => {
+                    let result_string = if conflict.fields.is_empty() {
+                        self.with_conflict_note(format!("Resolved the conflict on {}", conflict.item.describe()))
+                    } else {
+                        format!("{} field(s) of {} still need a side", conflict.fields.len(), conflict.item.describe())
+                    };
+                    CommandResult {
+                        result_code: CommandResultCode::Success,
+                        result: Some(conflict),
+                        result_string: Some(result_string),
+                        last_synced_instant_seconds: None,
+                    }
+                }
+
+                Err(why) => CommandResult {
+                    result_code: CommandResultCode::Failed,
+                    result: None,
+                    result_string: Some(why.to_string()),
+                    last_synced_instant_seconds: None,
+                },
+            }
+        }
+
+        /**
+         * Syncs the board called `name`: only what changed since its last sync, or all of it with `full`.
+         */
+        pub async fn sync_board(&mut self, name: &str, full: bool) -> CommandResult<BoardSnapshot> {
+            match self.board_service.sync_board(name, full).await {
+                Ok(snapshot) => CommandResult {
+                    result_code: CommandResultCode::Success,
+                    result_string: Some(format!("Synced board {}: {}", snapshot.board.name, snapshot.describe())),
+                    result: Some(snapshot),
+                    last_synced_instant_seconds: None,
+                },
+
+                Err(why) => CommandResult {
+                    result_code: CommandResultCode::Failed,
+                    result: None,
+                    result_string: Some(why.to_string()),
+                    last_synced_instant_seconds: None,
+                },
+            }
+        }
+
+        pub async fn sync_all_boards(&mut self, full: bool) -> CommandResult<Vec<BoardSnapshot>> {
+            match self.board_service.sync_all_boards(full).await {
+                Ok(snapshots) => CommandResult {
+                    result_code: CommandResultCode::Success,
+                    result_string: Some(format!("Synced {} board(s)", snapshots.len())),
+                    result: Some(snapshots),
+                    last_synced_instant_seconds: None,
+                },
+
+                Err(why) => CommandResult {
+                    result_code: CommandResultCode::Failed,
+                    result: None,
+                    result_string: Some(why.to_string()),
+                    last_synced_instant_seconds: None,
+                },
+            }
+        }
+
+        pub async fn migrate_local_store(&self, dry_run: bool) -> CommandResult<MigrationReport> {
+            match self.board_service.migrate_local_store(dry_run).await {
+                Ok(report) => CommandResult {
+                    result_code: CommandResultCode::Success,
+                    result_string: Some(if report.steps.is_empty() {
+                        format!("The local store is at schema version {}, nothing to migrate", report.from_version)
+                    } else if report.dry_run {
+                        format!("Would migrate the local store from schema version {} to {}", report.from_version, report.to_version)
+                    } else {
+                        format!("Migrated the local store from schema version {} to {}", report.from_version, report.to_version)
+                    }),
+                    result: Some(report),
+                    last_synced_instant_seconds: None,
+                },
+
+                Err(why) => CommandResult {
+                    result_code: CommandResultCode::Failed,
+                    result: None,
+                    result_string: Some(why.to_string()),
+                    last_synced_instant_seconds: None,
+                },
+            }
+        }
+
+        pub async fn purge_tombstones(&mut self) -> CommandResult<usize> {
+            match self.board_service.purge_tombstones().await {
+                Ok(purged) => CommandResult {
+                    result_code: CommandResultCode::Success,
+                    result: Some(purged),
+                    result_string: Some(format!("Deleted {} local item(s) that are gone from Trello", purged)),
+                    last_synced_instant_seconds: None,
+                },
+
+                Err(why) => CommandResult {
+                    result_code: CommandResultCode::Failed,
+                    result: None,
+                    result_string: Some(why.to_string()),
+                    last_synced_instant_seconds: None,
+                },
+            }
         }
 
         pub async fn get_all_boards(&mut self) -> CommandResult<Vec<Board>> {
@@ -36,6 +249,7 @@ pub mod command_executor {
                         result_code: result_code,
                         result: Some(boards),
                         result_string: Some(result_string),
+                        last_synced_instant_seconds: self.board_service.local_read_last_synced(),
                     }
                 }
 
@@ -46,6 +260,7 @@ pub mod command_executor {
                         result_code: result_code,
                         result: None,
                         result_string: Some(result_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
             };
@@ -64,12 +279,14 @@ pub mod command_executor {
                             result_code: CommandResultCode::Success,
                             result: Some(_board),
                             result_string: Some(res_string),
+                            last_synced_instant_seconds: None,
                         }
                     } else {
                         CommandResult {
                             result_code: CommandResultCode::Failed,
                             result: None,
                             result_string: Some(String::from("Failed to select board")),
+                            last_synced_instant_seconds: None,
                         }
                     }
                 }
@@ -78,6 +295,7 @@ pub mod command_executor {
                     result_code: CommandResultCode::Failed,
                     result: None,
                     result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 },
             };
 
@@ -93,6 +311,7 @@ pub mod command_executor {
                         result_code: CommandResultCode::Success,
                         result: Some(board),
                         result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
@@ -100,6 +319,7 @@ pub mod command_executor {
                     result_code: CommandResultCode::Failed,
                     result: None,
                     result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 },
             };
 
@@ -114,14 +334,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(labels),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: self.board_service.local_read_last_synced(),
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -136,14 +358,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(()),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -158,14 +382,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(label),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -180,14 +406,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(label),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -202,14 +430,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(lists),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: self.board_service.local_read_last_synced(),
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -224,14 +454,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(list),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -249,12 +481,14 @@ pub mod command_executor {
                             result_code: CommandResultCode::Success,
                             result: Some(_list),
                             result_string: Some(res_string),
+                            last_synced_instant_seconds: None,
                         }
                     } else {
                         CommandResult {
                             result_code: CommandResultCode::Failed,
                             result: None,
                             result_string: Some(String::from("Failed to select list")),
+                            last_synced_instant_seconds: None,
                         }
                     }
                 }
@@ -263,6 +497,7 @@ pub mod command_executor {
                     result_code: CommandResultCode::Failed,
                     result: None,
                     result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 },
             };
 
@@ -277,14 +512,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(cards),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: self.board_service.local_read_last_synced(),
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -299,14 +536,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(card),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -324,12 +563,14 @@ pub mod command_executor {
                             result_code: CommandResultCode::Success,
                             result: Some(_card),
                             result_string: Some(res_string),
+                            last_synced_instant_seconds: None,
                         }
                     } else {
                         CommandResult {
                             result_code: CommandResultCode::Failed,
                             result: None,
                             result_string: Some(String::from("Failed to select card")),
+                            last_synced_instant_seconds: None,
                         }
                     }
                 }
@@ -338,6 +579,7 @@ pub mod command_executor {
                     result_code: CommandResultCode::Failed,
                     result: None,
                     result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 },
             };
 
@@ -348,18 +590,20 @@ pub mod command_executor {
             let card_result = self.board_service.update_card(card).await;
             let command_result: CommandResult<Card> = match card_result {
                 Ok(card) => {
-                    let res_string = format!("Updated card");
+                    let res_string = self.with_conflict_note(format!("Updated card"));
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(card),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -384,7 +628,8 @@ pub mod command_executor {
                 return CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(format!("Failed to get the target list"))
+                    result_string: Some(format!("Failed to get the target list")),
+                    last_synced_instant_seconds: None,
                 };
             } else {
                 // Update the card
@@ -401,13 +646,15 @@ pub mod command_executor {
                 return CommandResult {
                     result_code: CommandResultCode::Success,
                     result: Some(all_labels),
-                    result_string: Some(format!("Retrieved card label(s)"))
+                    result_string: Some(format!("Retrieved card label(s)")),
+                    last_synced_instant_seconds: None,
                 }
             } else {
                 return CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(format!("Failed to get card labels"))
+                    result_string: Some(format!("Failed to get card labels")),
+                    last_synced_instant_seconds: None,
                 }
             }
         }
@@ -431,14 +678,16 @@ pub mod command_executor {
                     return CommandResult {
                         result_code: CommandResultCode::Failed,
                         result: None,
-                        result_string: Some(format!("Could not find a board label with the given name"))
+                        result_string: Some(format!("Could not find a board label with the given name")),
+                        last_synced_instant_seconds: None,
                     };
                 }
             } else {
                 return CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(format!("Failed to get board labels"))
+                    result_string: Some(format!("Failed to get board labels")),
+                    last_synced_instant_seconds: None,
                 };
             }
         }
@@ -462,14 +711,16 @@ pub mod command_executor {
                     return CommandResult {
                         result_code: CommandResultCode::Failed,
                         result: None,
-                        result_string: Some(format!("Could not find a card label with the given name"))
+                        result_string: Some(format!("Could not find a card label with the given name")),
+                        last_synced_instant_seconds: None,
                     };
                 }
             } else {
                 return CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(format!("Failed to get card labels"))
+                    result_string: Some(format!("Failed to get card labels")),
+                    last_synced_instant_seconds: None,
                 };
             }
         }
@@ -482,14 +733,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(comments),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: self.board_service.local_read_last_synced(),
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -504,14 +757,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(comment),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -527,14 +782,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(checklists),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: self.board_service.local_read_last_synced(),
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -550,14 +807,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(checklist),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -576,12 +835,14 @@ pub mod command_executor {
                             result_code: CommandResultCode::Success,
                             result: Some(_checklist),
                             result_string: Some(res_string),
+                            last_synced_instant_seconds: None,
                         }
                     } else {
                         CommandResult {
                             result_code: CommandResultCode::Failed,
                             result: None,
                             result_string: Some(String::from("Failed to select checklist")),
+                            last_synced_instant_seconds: None,
                         }
                     }
                 }
@@ -590,6 +851,7 @@ pub mod command_executor {
                     result_code: CommandResultCode::Failed,
                     result: None,
                     result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 },
             };
 
@@ -604,14 +866,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(tasks),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: self.board_service.local_read_last_synced(),
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -626,14 +890,16 @@ pub mod command_executor {
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(task),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -644,18 +910,20 @@ pub mod command_executor {
             let task_result = self.board_service.update_checklist_task(card, task).await;
             let command_result: CommandResult<CardChecklistTask> = match task_result {
                 Ok(task) => {
-                    let res_string = format!("Updated task");
+                    let res_string = self.with_conflict_note(format!("Updated task"));
                     CommandResult {
                         result_code: CommandResultCode::Success,
                         result: Some(task),
-                        result_string: Some(res_string)
+                        result_string: Some(res_string),
+                        last_synced_instant_seconds: None,
                     }
                 }
 
                 Err(why) => CommandResult {
                     result_code: CommandResultCode::Failed,
                     result: None,
-                    result_string: Some(String::from(why.to_string()))
+                    result_string: Some(String::from(why.to_string())),
+                    last_synced_instant_seconds: None,
                 }
             };
 
@@ -663,3 +931,39 @@ pub mod command_executor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::command_executor::CommandExecutor;
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn unknown_stores_are_reported() {
+        let config = Some(json!({ "stores": { "remote": "memory", "local": "floppy" } }));
+        assert!(CommandExecutor::new(config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn cards_move_between_lists_without_trello() {
+        let config = Some(json!({ "stores": { "remote": "memory", "local": "memory" } }));
+        let mut executor = CommandExecutor::new(config).await.unwrap();
+        executor.create_board("Eng").await;
+        executor.select_board("Eng").await;
+        executor.create_board_list(None, "Todo").await;
+        executor.create_board_list(None, "Done").await;
+        executor.select_board_list("Todo", None).await;
+        let card = executor.create_list_card(None, "Fix login").await.result.unwrap();
+
+        let moved = executor.move_card_to_list(card, "Done").await;
+        assert!(matches!(moved.result_code, CommandResultCode::Success));
+
+        let done = executor.select_board_list("Done", None).await.result.unwrap();
+        let cards = executor.get_all_list_cards(Some(done)).await.result.unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].name, "Fix login");
+
+        let missing = executor.move_card_to_list(cards[0].clone(), "Blocked").await;
+        assert!(matches!(missing.result_code, CommandResultCode::Failed));
+    }
+}
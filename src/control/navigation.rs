@@ -0,0 +1,83 @@
+/**
+ * Depth of the deepest node in the board hierarchy: board, list, card, checklist.
+ */
+pub const MAX_DEPTH: usize = 4;
+
+/**
+ * Resolves `path` against the `current` node, given as the names from the board down.
+ * Paths starting with `/` are absolute. `.` stays on the current node and `..` goes up one level
+ * (going up from the root stays at the root, like in a shell).
+ */
+pub fn resolve_path(current: &[String], path: &str) -> Result<Vec<String>, String> {
+    let mut resolved: Vec<String> = if path.starts_with('/') {
+        vec![]
+    } else {
+        current.to_vec()
+    };
+
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                resolved.pop();
+            }
+            name => {
+                if resolved.len() == MAX_DEPTH {
+                    return Err(format!("Checklists have no children. Cannot go into '{}'", name));
+                }
+                resolved.push(name.to_string());
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+pub fn format_path(names: &[String]) -> String {
+    format!("/{}", names.join("/"))
+}
+
+/**
+ * Number of leading names `a` and `b` have in common. Names are compared ignoring case,
+ * the same way the select commands match them.
+ */
+pub fn common_depth(a: &[String], b: &[String]) -> usize {
+    a.iter()
+        .zip(b.iter())
+        .take_while(|(a_name, b_name)| a_name.eq_ignore_ascii_case(b_name))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn resolves_relative_and_absolute_paths() {
+        let current = path(&["Eng", "Todo"]);
+        assert_eq!(resolve_path(&current, "Fix login"), Ok(path(&["Eng", "Todo", "Fix login"])));
+        assert_eq!(resolve_path(&current, "../Done/"), Ok(path(&["Eng", "Done"])));
+        assert_eq!(resolve_path(&current, "/Ops/./Backlog"), Ok(path(&["Ops", "Backlog"])));
+        assert_eq!(resolve_path(&current, "/"), Ok(vec![]));
+        assert_eq!(resolve_path(&current, "../../.."), Ok(vec![]));
+    }
+
+    #[test]
+    fn rejects_paths_below_checklists() {
+        let current = path(&["Eng", "Todo", "Fix login", "Steps"]);
+        assert!(resolve_path(&current, "Write test").is_err());
+        assert_eq!(resolve_path(&current, "../Other"), Ok(path(&["Eng", "Todo", "Fix login", "Other"])));
+    }
+
+    #[test]
+    fn common_depth_ignores_case() {
+        assert_eq!(common_depth(&path(&["Eng", "Todo"]), &path(&["eng", "TODO", "Card"])), 2);
+        assert_eq!(common_depth(&path(&["Eng", "Todo"]), &path(&["Eng", "Done"])), 1);
+        assert_eq!(format_path(&path(&["Eng", "Todo"])), "/Eng/Todo");
+        assert_eq!(format_path(&[]), "/");
+    }
+}
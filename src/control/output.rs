@@ -0,0 +1,207 @@
+use crate::control::*;
+use crate::data::SyncConflict;
+
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+    Tsv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(format!("Unknown output format '{}'. Expected one of json, yaml, tsv or text", s)),
+        }
+    }
+}
+
+/**
+ * Prints a command result in one of the machine-readable formats.
+ * JSON and YAML carry the whole `CommandResult`, including the result code and string.
+ * TSV only carries the payload (one row per item, nested fields flattened into dotted
+ * column names); the result string of a failed command is written to stderr instead.
+ */
+pub fn print_structured<T: Serialize>(format: OutputFormat, result: &CommandResult<T>) {
+    match format {
+        OutputFormat::Text => {
+            println!("{}", result.result_string.clone().unwrap_or_default());
+            if let Some(last_synced) = result.last_synced_instant_seconds {
+                eprintln!("{}", stale_note(last_synced));
+            }
+        }
+
+        OutputFormat::Json => match serde_json::to_string_pretty(result) {
+            Ok(json) => println!("{}", json),
+            Err(why) => eprintln!("Failed to serialize result: {}", why),
+        },
+
+        OutputFormat::Yaml => match serde_yaml::to_string(result) {
+            Ok(yaml) => print!("{}", yaml),
+            Err(why) => eprintln!("Failed to serialize result: {}", why),
+        },
+
+        OutputFormat::Tsv => {
+            if let CommandResultCode::Failed = result.result_code {
+                eprintln!("{}", result.result_string.clone().unwrap_or_default());
+                return;
+            }
+
+            match serde_json::to_value(&result.result) {
+                Ok(payload) => print!("{}", to_tsv(&payload)),
+                Err(why) => eprintln!("Failed to serialize result: {}", why),
+            }
+        }
+    }
+}
+
+/**
+ * A conflict as shown in the list of `sync conflicts`: its number, the item and both values of each field.
+ */
+pub fn describe_conflict(number: usize, conflict: &SyncConflict) -> String {
+    let mut description = format!("{}. {}", number, conflict.item.describe());
+    for field in &conflict.fields {
+        description.push_str(&format!("\n     {}: local {}, trello {}", field.field, field.local, field.remote));
+    }
+    description
+}
+
+/**
+ * Tells the user a result was read from the local store because Trello could not be reached.
+ */
+pub fn stale_note(last_synced_instant_seconds: i64) -> String {
+    match Local.timestamp_opt(last_synced_instant_seconds, 0).single() {
+        Some(last_synced) if last_synced_instant_seconds != 0 => {
+            format!("Trello could not be reached. Showing the local copy, last synced {}", last_synced.to_rfc2822())
+        }
+        _ => String::from("Trello could not be reached. Showing the local copy, which was never synced"),
+    }
+}
+
+fn to_tsv(payload: &Value) -> String {
+    let rows: Vec<Map<String, Value>> = match payload {
+        Value::Null => vec![],
+        Value::Array(items) => items.iter().map(flatten).collect(),
+        other => vec![flatten(other)],
+    };
+
+    if rows.is_empty() {
+        return String::new();
+    }
+
+    let mut columns: Vec<String> = vec![];
+    for row in &rows {
+        for key in row.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    let mut tsv = columns.join("\t");
+    tsv.push('\n');
+    for row in &rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| row.get(column).map(tsv_cell).unwrap_or_default())
+            .collect();
+        tsv.push_str(&cells.join("\t"));
+        tsv.push('\n');
+    }
+
+    tsv
+}
+
+fn flatten(value: &Value) -> Map<String, Value> {
+    let mut flattened = Map::new();
+    match value {
+        Value::Object(_) => flatten_into(&mut flattened, "", value),
+        other => {
+            flattened.insert(String::from("value"), other.clone());
+        }
+    }
+    flattened
+}
+
+fn flatten_into(flattened: &mut Map<String, Value>, prefix: &str, value: &Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, inner) in object {
+                let column = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_into(flattened, &column, inner);
+            }
+        }
+
+        other => {
+            flattened.insert(prefix.to_string(), other.clone());
+        }
+    }
+}
+
+fn tsv_cell(value: &Value) -> String {
+    let raw = match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    raw.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn tsv_flattens_nested_ids() {
+        let payload = json!([
+            { "_id": { "trello_id": "abc", "local_id": null }, "name": "Eng" },
+            { "_id": { "trello_id": "def", "local_id": "1" }, "name": "Ops" }
+        ]);
+
+        assert_eq!(
+            to_tsv(&payload),
+            "_id.trello_id\t_id.local_id\tname\nabc\t\tEng\ndef\t1\tOps\n"
+        );
+    }
+
+    #[test]
+    fn tsv_escapes_tabs_and_newlines() {
+        let payload = json!({ "description": "line one\nline\ttwo" });
+        assert_eq!(to_tsv(&payload), "description\nline one\\nline\\ttwo\n");
+    }
+
+    #[test]
+    fn stale_note_mentions_when_the_copy_was_synced() {
+        assert!(stale_note(0).ends_with("never synced"));
+        assert!(stale_note(1_700_000_000).contains("2023"));
+    }
+
+    #[test]
+    fn tsv_of_empty_payload_is_empty() {
+        assert_eq!(to_tsv(&Value::Null), "");
+        assert_eq!(to_tsv(&json!([])), "");
+    }
+}
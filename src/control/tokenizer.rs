@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+
+/**
+ * Arguments of a shell command line, split the way a POSIX shell would split them.
+ * Words can be quoted with single or double quotes and characters can be escaped with a backslash.
+ * Inside double quotes `\n` and `\t` stand for a newline and a tab.
+ * Unquoted words of the form `--flag=value` (or just `--flag`) are options rather than words;
+ * a bare `--` turns the rest of the line into plain words.
+ */
+#[derive(Debug, Default, PartialEq)]
+pub struct Arguments {
+    words: VecDeque<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Arguments {
+    pub fn parse(input: &str) -> Result<Arguments, String> {
+        let mut arguments = Arguments::default();
+        let mut options_ended = false;
+
+        for (token, quoted) in tokenize(input)? {
+            if !quoted && !options_ended && token == "--" {
+                options_ended = true;
+            } else if !quoted && !options_ended && token.starts_with("--") {
+                let option = &token[2..];
+                match option.split_once('=') {
+                    Some((name, value)) => arguments.options.push((name.to_string(), Some(value.to_string()))),
+                    None => arguments.options.push((option.to_string(), None)),
+                }
+            } else {
+                arguments.words.push_back(token);
+            }
+        }
+
+        Ok(arguments)
+    }
+
+    /**
+     * Takes all remaining words joined by single spaces.
+     * A single quoted word is returned exactly as written, including repeated spaces and newlines.
+     */
+    pub fn remainder(&mut self) -> String {
+        let words: Vec<String> = self.words.drain(..).collect();
+        words.join(" ")
+    }
+
+    /**
+     * The value of the option `--name=value`. The last occurrence wins.
+     */
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /**
+     * Whether the option `--name` was given, with or without a value.
+     */
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+}
+
+impl Iterator for Arguments {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.words.pop_front()
+    }
+}
+
+impl DoubleEndedIterator for Arguments {
+    fn next_back(&mut self) -> Option<String> {
+        self.words.pop_back()
+    }
+}
+
+/**
+ * Splits `input` into words. Each word is returned with whether any part of it was quoted or escaped.
+ */
+fn tokenize(input: &str) -> Result<Vec<(String, bool)>, String> {
+    let mut tokens: Vec<(String, bool)> = vec![];
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quoted = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_token = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(inner) => current.push(inner),
+                        None => return Err(String::from("Missing closing single quote")),
+                    }
+                }
+            }
+
+            '"' => {
+                in_token = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('t') => current.push('\t'),
+                            Some(escaped) => current.push(escaped),
+                            None => return Err(String::from("Missing closing double quote")),
+                        },
+                        Some(inner) => current.push(inner),
+                        None => return Err(String::from("Missing closing double quote")),
+                    }
+                }
+            }
+
+            '\\' => {
+                in_token = true;
+                quoted = true;
+                match chars.next() {
+                    Some(escaped) => current.push(escaped),
+                    None => return Err(String::from("Nothing to escape after the trailing backslash")),
+                }
+            }
+
+            c if c.is_whitespace() => {
+                if in_token {
+                    tokens.push((std::mem::take(&mut current), quoted));
+                    in_token = false;
+                    quoted = false;
+                }
+            }
+
+            c => {
+                in_token = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_token {
+        tokens.push((current, quoted));
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(input: &str) -> Vec<String> {
+        Arguments::parse(input).unwrap().collect()
+    }
+
+    #[test]
+    fn splits_on_whitespace_and_honours_quotes() {
+        assert_eq!(words("label create 'In Progress'  yellow"), vec!["label", "create", "In Progress", "yellow"]);
+        assert_eq!(words(r#"card add-comment "two  spaces" it\'s"#), vec!["card", "add-comment", "two  spaces", "it's"]);
+        assert_eq!(words(r#"a"b c"d"#), vec!["ab cd"]);
+        assert_eq!(words("''"), vec![""]);
+    }
+
+    #[test]
+    fn expands_escapes_in_double_quotes_only() {
+        assert_eq!(words(r#""line one\nline two""#), vec!["line one\nline two"]);
+        assert_eq!(words(r#"'line one\nline two'"#), vec![r"line one\nline two"]);
+    }
+
+    #[test]
+    fn separates_options_from_words() {
+        let mut arguments = Arguments::parse("create --color=green Needs Review --verbose '--not-an-option' -- --plain").unwrap();
+        assert_eq!(arguments.option("color"), Some("green"));
+        assert_eq!(arguments.option("verbose"), None);
+        assert_eq!(arguments.next().as_deref(), Some("create"));
+        assert_eq!(arguments.remainder(), "Needs Review --not-an-option --plain");
+    }
+
+    #[test]
+    fn reports_unterminated_quotes() {
+        assert!(Arguments::parse("card select 'Fix login").is_err());
+        assert!(Arguments::parse("card select \"Fix login").is_err());
+        assert!(Arguments::parse("card select Fix\\").is_err());
+    }
+}
@@ -0,0 +1,455 @@
+use crate::control::command_executor::CommandExecutor;
+use crate::control::command_line::format_due_date;
+use crate::control::*;
+use crate::data::*;
+
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind};
+use futures::StreamExt;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use std::io::{self, IsTerminal};
+
+const HELP: &str = "←/→ h/l: column  ↑/↓ j/k: card  </>: move card  Enter: details  r: reload  q: quit";
+const PICKER_HELP: &str = "↑/↓ j/k: board  Enter: open  q: quit";
+
+struct Column {
+    list: BoardList,
+    cards: Vec<Card>,
+}
+
+struct CardDetails {
+    labels: Vec<CardLabel>,
+    checklists: Vec<(CardChecklist, Vec<CardChecklistTask>)>,
+}
+
+struct Tui {
+    command_exec: CommandExecutor,
+    boards: Vec<Board>,
+    selected_board: usize,
+    board: Option<Board>,
+    board_labels: Vec<CardLabel>,
+    columns: Vec<Column>,
+    selected_column: usize,
+    selected_card: usize,
+    show_details: bool,
+    details: Option<CardDetails>,
+    status: String,
+    quit: bool,
+}
+
+/**
+ * Keeps `selected` a valid index into a collection of `len` items (0 when it is empty).
+ */
+fn clamp_selection(selected: usize, len: usize) -> usize {
+    selected.min(len.saturating_sub(1))
+}
+
+/**
+ * Index of the column `offset` columns away from `selected`, or `None` when that would leave the board.
+ */
+fn neighbour_column(selected: usize, offset: isize, len: usize) -> Option<usize> {
+    let target = selected as isize + offset;
+    if target < 0 || target as usize >= len {
+        None
+    } else {
+        Some(target as usize)
+    }
+}
+
+impl Tui {
+    fn new(command_exec: CommandExecutor) -> Tui {
+        Tui {
+            command_exec,
+            boards: vec![],
+            selected_board: 0,
+            board: None,
+            board_labels: vec![],
+            columns: vec![],
+            selected_column: 0,
+            selected_card: 0,
+            show_details: false,
+            details: None,
+            status: String::from(PICKER_HELP),
+            quit: false,
+        }
+    }
+
+    fn current_card(&self) -> Option<&Card> {
+        self.columns
+            .get(self.selected_column)
+            .and_then(|column| column.cards.get(self.selected_card))
+    }
+
+    fn report_failure<T>(&mut self, result: &CommandResult<T>) -> bool {
+        if let CommandResultCode::Failed = result.result_code {
+            self.status = result.result_string.clone().unwrap_or_default();
+            true
+        } else {
+            false
+        }
+    }
+
+    async fn load_boards(&mut self) {
+        let boards_result = self.command_exec.get_all_boards().await;
+        if !self.report_failure(&boards_result) {
+            self.boards = boards_result.result.unwrap_or_default();
+            self.selected_board = clamp_selection(self.selected_board, self.boards.len());
+        }
+    }
+
+    async fn open_board(&mut self, name: &str) {
+        let board_result = self.command_exec.select_board(name).await;
+        if self.report_failure(&board_result) {
+            return;
+        }
+
+        self.board = board_result.result;
+        self.selected_column = 0;
+        self.selected_card = 0;
+        self.load_board().await;
+    }
+
+    async fn load_board(&mut self) {
+        let board = match self.board.clone() {
+            Some(board) => board,
+            None => return,
+        };
+
+        let lists_result = self.command_exec.get_all_board_lists(Some(board.clone())).await;
+        if self.report_failure(&lists_result) {
+            return;
+        }
+
+        let mut last_synced = lists_result.last_synced_instant_seconds;
+        let mut columns: Vec<Column> = vec![];
+        for list in lists_result.result.unwrap_or_default() {
+            let cards_result = self.command_exec.get_all_list_cards(Some(list.clone())).await;
+            if self.report_failure(&cards_result) {
+                return;
+            }
+            last_synced = last_synced.or(cards_result.last_synced_instant_seconds);
+            columns.push(Column { list, cards: cards_result.result.unwrap_or_default() });
+        }
+
+        let labels_result = self.command_exec.get_all_board_labels(Some(board)).await;
+        self.board_labels = labels_result.result.unwrap_or_default();
+        self.columns = columns;
+        self.selected_column = clamp_selection(self.selected_column, self.columns.len());
+        self.select_card(self.selected_card).await;
+        self.status = match last_synced {
+            Some(last_synced) => output::stale_note(last_synced),
+            None => String::from(HELP),
+        };
+    }
+
+    async fn select_card(&mut self, index: usize) {
+        let card_count = self.columns.get(self.selected_column).map_or(0, |column| column.cards.len());
+        self.selected_card = clamp_selection(index, card_count);
+        self.details = None;
+        if self.show_details {
+            self.load_details().await;
+        }
+    }
+
+    async fn load_details(&mut self) {
+        let card = match self.current_card() {
+            Some(card) => card.clone(),
+            None => return,
+        };
+
+        let labels: Vec<CardLabel> = self
+            .board_labels
+            .iter()
+            .filter(|label| card.label_ids.contains(&label._id))
+            .cloned()
+            .collect();
+
+        let checklists_result = self.command_exec.get_card_checklists(Some(card)).await;
+        if self.report_failure(&checklists_result) {
+            return;
+        }
+
+        let mut checklists: Vec<(CardChecklist, Vec<CardChecklistTask>)> = vec![];
+        for checklist in checklists_result.result.unwrap_or_default() {
+            let tasks_result = self.command_exec.get_checklist_tasks(Some(checklist.clone())).await;
+            checklists.push((checklist, tasks_result.result.unwrap_or_default()));
+        }
+
+        self.details = Some(CardDetails { labels, checklists });
+    }
+
+    async fn move_card(&mut self, offset: isize) {
+        let target = match neighbour_column(self.selected_column, offset, self.columns.len()) {
+            Some(target) => target,
+            None => return,
+        };
+        let card = match self.current_card() {
+            Some(card) => card.clone(),
+            None => return,
+        };
+
+        let target_name = self.columns[target].list.name.clone();
+        let move_result = self.command_exec.move_card_to_list(card, &target_name).await;
+        if self.report_failure(&move_result) {
+            return;
+        }
+
+        self.columns[self.selected_column].cards.remove(self.selected_card);
+        self.columns[target].cards.push(move_result.result.unwrap());
+        self.selected_column = target;
+        self.status = format!("Moved card to {}", target_name);
+        self.select_card(self.columns[target].cards.len() - 1).await;
+    }
+
+    async fn handle_picker_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => self.selected_board = self.selected_board.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected_board = clamp_selection(self.selected_board + 1, self.boards.len())
+            }
+            KeyCode::Enter => {
+                if let Some(board) = self.boards.get(self.selected_board) {
+                    let name = board.name.clone();
+                    self.open_board(&name).await;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_board_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc => {
+                if self.show_details {
+                    self.show_details = false;
+                } else {
+                    self.quit = true;
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                if let Some(column) = neighbour_column(self.selected_column, -1, self.columns.len()) {
+                    self.selected_column = column;
+                    self.select_card(self.selected_card).await;
+                }
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(column) = neighbour_column(self.selected_column, 1, self.columns.len()) {
+                    self.selected_column = column;
+                    self.select_card(self.selected_card).await;
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.select_card(self.selected_card.saturating_sub(1)).await,
+            KeyCode::Down | KeyCode::Char('j') => self.select_card(self.selected_card + 1).await,
+            KeyCode::Char('<') | KeyCode::Char('H') => self.move_card(-1).await,
+            KeyCode::Char('>') | KeyCode::Char('L') => self.move_card(1).await,
+            KeyCode::Enter => {
+                self.show_details = !self.show_details;
+                if self.show_details {
+                    self.load_details().await;
+                }
+            }
+            KeyCode::Char('r') => self.load_board().await,
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main_area, status_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        frame.render_widget(Paragraph::new(self.status.as_str()), status_area);
+
+        match &self.board {
+            None => self.draw_picker(frame, main_area),
+            Some(board) => {
+                let [board_area, details_area] = if self.show_details {
+                    Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(main_area)
+                } else {
+                    [main_area, Rect::default()]
+                };
+
+                self.draw_columns(frame, board_area, board);
+                if self.show_details {
+                    self.draw_details(frame, details_area);
+                }
+            }
+        }
+    }
+
+    fn draw_picker(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self.boards.iter().map(|board| ListItem::new(board.name.as_str())).collect();
+        let list = List::new(items)
+            .block(Block::bordered().title("Boards"))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.selected_board));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn draw_columns(&self, frame: &mut Frame, area: Rect, board: &Board) {
+        let block = Block::bordered().title(board.name.as_str());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let constraints = vec![Constraint::Ratio(1, self.columns.len() as u32); self.columns.len()];
+        let column_areas = Layout::horizontal(constraints).split(inner);
+        for (index, (column, column_area)) in self.columns.iter().zip(column_areas.iter()).enumerate() {
+            let is_selected = index == self.selected_column;
+            let items: Vec<ListItem> = column.cards.iter().map(|card| ListItem::new(card.name.as_str())).collect();
+            let title_style = if is_selected {
+                Style::new().add_modifier(Modifier::BOLD)
+            } else {
+                Style::new()
+            };
+            let list = List::new(items)
+                .block(Block::bordered().title(Line::styled(column.list.name.as_str(), title_style)))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default().with_selected(if is_selected { Some(self.selected_card) } else { None });
+            frame.render_stateful_widget(list, *column_area, &mut state);
+        }
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let mut text = Text::default();
+        if let Some(card) = self.current_card() {
+            text.push_line(Line::styled(card.name.as_str(), Style::new().add_modifier(Modifier::BOLD)));
+            text.push_line(Line::from(format!(
+                "Due: {}{}",
+                format_due_date(card.due_date_instant_seconds),
+                if card.due_complete { " (complete)" } else { "" }
+            )));
+
+            if let Some(details) = &self.details {
+                let label_names: Vec<&str> = details.labels.iter().map(|label| label.name.as_str()).collect();
+                text.push_line(Line::from(format!("Labels: {}", label_names.join(", "))));
+            }
+
+            text.push_line(Line::default());
+            for line in card.description.lines() {
+                text.push_line(Line::from(line));
+            }
+
+            if let Some(details) = &self.details {
+                for (checklist, tasks) in &details.checklists {
+                    text.push_line(Line::default());
+                    text.push_line(Line::styled(checklist.name.as_str(), Style::new().add_modifier(Modifier::BOLD)));
+                    for task in tasks {
+                        text.push_line(Line::from(format!("[{}] {}", if task.is_complete { "x" } else { " " }, task.name)));
+                    }
+                }
+            }
+        }
+
+        let paragraph = Paragraph::new(text)
+            .block(Block::bordered().title("Details"))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, area);
+    }
+
+    async fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        let mut events = EventStream::new();
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let event = match events.next().await {
+                Some(event) => event?,
+                None => break,
+            };
+            if let Event::Key(key) = event {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                if self.board.is_none() {
+                    self.handle_picker_key(key).await;
+                } else {
+                    self.handle_board_key(key).await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * Shows `board_name` (or a picker of all boards when no name is given) as a full-screen kanban board.
+ */
+pub async fn run(command_exec: CommandExecutor, board_name: Option<String>) -> CommandResultCode {
+    if !io::stdout().is_terminal() {
+        eprintln!("The TUI needs to run in a terminal");
+        return CommandResultCode::Failed;
+    }
+
+    let mut tui = Tui::new(command_exec);
+
+    match board_name {
+        Some(name) => {
+            tui.open_board(&name).await;
+            if tui.board.is_none() {
+                eprintln!("Could not open board {}: {}", name, tui.status);
+                return CommandResultCode::Failed;
+            }
+        }
+        None => tui.load_boards().await,
+    }
+
+    let mut terminal = ratatui::init();
+    let run_result = tui.run(&mut terminal).await;
+    ratatui::restore();
+
+    match run_result {
+        Ok(()) => CommandResultCode::Success,
+        Err(why) => {
+            eprintln!("Terminal error: {}", why);
+            CommandResultCode::Failed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn selection_stays_within_bounds() {
+        assert_eq!(clamp_selection(5, 3), 2);
+        assert_eq!(clamp_selection(1, 3), 1);
+        assert_eq!(clamp_selection(4, 0), 0);
+
+        assert_eq!(neighbour_column(0, -1, 3), None);
+        assert_eq!(neighbour_column(0, 1, 3), Some(1));
+        assert_eq!(neighbour_column(2, 1, 3), None);
+    }
+
+    #[tokio::test]
+    async fn cards_move_to_the_next_column() {
+        let config = Some(json!({ "stores": { "remote": "memory", "local": "memory" } }));
+        let mut command_exec = CommandExecutor::new(config).await.unwrap();
+        command_exec.create_board("Eng").await;
+        command_exec.select_board("Eng").await;
+        command_exec.create_board_list(None, "Todo").await;
+        command_exec.create_board_list(None, "Done").await;
+        command_exec.select_board_list("Todo", None).await;
+        command_exec.create_list_card(None, "Fix login").await;
+
+        let mut tui = Tui::new(command_exec);
+        tui.open_board("Eng").await;
+        tui.handle_board_key(KeyEvent::from(KeyCode::Char('>'))).await;
+
+        assert_eq!(tui.selected_column, 1);
+        assert!(tui.columns[0].cards.is_empty());
+        let done = tui.columns[1].list.clone();
+        let cards = tui.command_exec.get_all_list_cards(Some(done)).await.result.unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].name, "Fix login");
+    }
+}
@@ -1,14 +1,25 @@
+use crate::data::memory_data_store::MemoryDataStore;
+use crate::data::merge::merge;
 use crate::data::mongo_data_store::MongoDataStore;
+use crate::data::sqlite_data_store::SqliteDataStore;
 use crate::data::trello_data_store::TrelloDataStore;
 use crate::data::*;
 
 use async_trait::async_trait;
+use chrono::{Duration, SecondsFormat, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
 
-use std::sync::Once;
+use std::collections::HashMap;
 
-static INIT: Once = Once::new();
+const DEFAULT_REMOTE_STORE: &str = "trello";
+const DEFAULT_LOCAL_STORE: &str = "mongodb";
 
 pub struct DataRepository {
+    remote: Box<dyn DataStore>,
+    local: Box<dyn DataStore>,
+
     active_board: Option<Board>,
     active_boardlist: Option<BoardList>,
     active_card: Option<Card>,
@@ -19,30 +30,685 @@ pub struct DataRepository {
     cache_cards: Option<Vec<Card>>,
     cache_checklists: Option<Vec<CardChecklist>>,
     cache_labels: Option<Vec<CardLabel>>,
+    cache_tasks: Option<Vec<CardChecklistTask>>,
+
+    local_read_last_synced: Option<i64>,
+    /** `local_read_last_synced` as it was when each cache was filled, so reads served from the cache keep it */
+    cache_read_last_synced: HashMap<&'static str, Option<i64>>,
+    purge_deleted: bool,
+    recorded_conflicts: usize,
 }
 
 impl DataRepository {
-    pub async fn new(config: Option<serde_json::Value>) -> Option<DataRepository> {
-        let mut dr: Option<DataRepository> = None;
-        MongoDataStore::init(config.clone()).await;
-
-        INIT.call_once(|| {
-            TrelloDataStore::init(config); // TODO: Change this
-
-            dr = Some(DataRepository {
-                active_board: None,
-                active_boardlist: None,
-                active_card: None,
-                active_checklist: None,
-                cache_boards: None,
-                cache_boardlists: None,
-                cache_cards: None,
-                cache_checklists: None,
-                cache_labels: None,
-            });
+    /**
+     * Creates a repository with the remote and local stores named in the `stores` section of the config,
+     * e.g. `"stores": { "remote": "trello", "local": "mongodb" }`. Missing entries use those defaults.
+     */
+    pub async fn new(config: Option<Value>) -> Result<DataRepository, Box<dyn std::error::Error>> {
+        let remote = DataRepository::store_from_config(&config, "remote", DEFAULT_REMOTE_STORE).await?;
+        let local = DataRepository::store_from_config(&config, "local", DEFAULT_LOCAL_STORE).await?;
+        let mut repository = DataRepository::with_stores(remote, local);
+        repository.purge_deleted = config
+            .as_ref()
+            .and_then(|config| config.get("sync"))
+            .and_then(|sync| sync.get("purge_deleted"))
+            .and_then(|purge_deleted| purge_deleted.as_bool())
+            .unwrap_or(false);
+        Ok(repository)
+    }
+
+    pub fn with_stores(remote: Box<dyn DataStore>, local: Box<dyn DataStore>) -> DataRepository {
+        DataRepository {
+            remote,
+            local,
+            active_board: None,
+            active_boardlist: None,
+            active_card: None,
+            active_checklist: None,
+            cache_boards: None,
+            cache_boardlists: None,
+            cache_cards: None,
+            cache_checklists: None,
+            cache_labels: None,
+            cache_tasks: None,
+            local_read_last_synced: None,
+            cache_read_last_synced: HashMap::new(),
+            purge_deleted: false,
+            recorded_conflicts: 0,
+        }
+    }
+
+    async fn store_from_config(config: &Option<Value>, role: &str, default_store: &str) -> Result<Box<dyn DataStore>, Box<dyn std::error::Error>> {
+        let store_name = config
+            .as_ref()
+            .and_then(|config| config.get("stores"))
+            .and_then(|stores| stores.get(role))
+            .and_then(|store| store.as_str())
+            .unwrap_or(default_store);
+
+        match store_name {
+            "trello" => Ok(Box::new(TrelloDataStore::new(config)?)),
+            "mongodb" => Ok(Box::new(MongoDataStore::new(config).await?)),
+            "sqlite" => Ok(Box::new(SqliteDataStore::new(config)?)),
+            "memory" if role == "remote" => Ok(Box::new(MemoryDataStore::remote())),
+            "memory" => Ok(Box::new(MemoryDataStore::local())),
+            _ => Err(Box::new(InvalidInputError { message: Some(format!("Unknown {} store '{}'", role, store_name)) })),
+        }
+    }
+
+    /**
+     * Whether a write can be sent to Trello now. While changes made offline are waiting in the outbox,
+     * new ones are queued behind them so Trello sees them in the order they were made.
+     */
+    async fn can_write_remote(&self) -> bool {
+        match self.local.get_outbox().await {
+            Ok(entries) => entries.is_empty(),
+            Err(_) => true,
+        }
+    }
+
+    async fn queue_change(&self, change: OutboxChange) -> Result<(), Box<dyn std::error::Error>> {
+        let last_sequence = self.local.get_outbox().await?.iter().map(|entry| entry.sequence).max();
+        let entry = OutboxEntry {
+            _id: ID { trello_id: None, local_id: new_local_id() },
+            change,
+            sequence: last_sequence.map_or(1, |sequence| sequence + 1),
+        };
+        self.local.add_to_outbox(&entry).await
+    }
+
+    /**
+     * Replays the changes in the outbox to Trello in the order they were made and returns how many were pushed.
+     * Stops at the first change that fails, which stays in the outbox with everything after it.
+     */
+    pub async fn push_outbox(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut pushed: usize = 0;
+        let mut assigned_ids: Vec<(String, String)> = vec![];
+        let mut push_result: Result<(), Box<dyn std::error::Error>> = Ok(());
+
+        loop {
+            // Read the outbox again after every push, the next change may refer to an item that just got its Trello id
+            let entry = match self.local.get_outbox().await?.into_iter().next() {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            match self.push_change(&entry.change).await {
+                Ok(assigned_id) => {
+                    assigned_ids.extend(assigned_id);
+                    self.local.remove_from_outbox(&entry._id).await?;
+                    pushed += 1;
+                }
+                Err(why) => {
+                    push_result = Err(format!("Pushed {} change(s). Failed to {}: {}", pushed, entry.change.describe(), why).into());
+                    break;
+                }
+            }
+        }
+
+        self.active_board = with_trello_ids(self.active_board.take(), &assigned_ids);
+        self.active_boardlist = with_trello_ids(self.active_boardlist.take(), &assigned_ids);
+        self.active_card = with_trello_ids(self.active_card.take(), &assigned_ids);
+        self.active_checklist = with_trello_ids(self.active_checklist.take(), &assigned_ids);
+        self.cache_boards.take();
+        self.invalidate_caches(true, true, true, true);
+        push_result.map(|_| pushed)
+    }
+
+    /**
+     * Sends one change to Trello. For a change that creates an item, returns its local id and the id Trello gave it.
+     */
+    async fn push_change(&mut self, change: &OutboxChange) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+        if change.referenced_ids().iter().any(|id| id.trello_id.is_none()) {
+            return Err(Box::new(InvalidInputError { message: Some(String::from("It refers to an item that is not on Trello yet")) }));
+        }
+
+        let created_ids: Option<(&ID, ID)> = match change {
+            OutboxChange::CreateBoard { board } => Some((&board._id, self.remote.create_board(&board.name, None).await?._id)),
+            OutboxChange::CreateLabel { label } => Some((
+                &label._id,
+                self.remote.create_board_label(label.board_id.clone(), &label.name, &label.color, None).await?._id,
+            )),
+            OutboxChange::UpdateLabel { label_id, name, color } => {
+                self.remote.update_board_label(label_id.clone(), name, color).await?;
+                None
+            }
+            OutboxChange::DeleteLabel { label_id } => {
+                self.remote.delete_board_label(label_id.clone()).await?;
+                None
+            }
+            OutboxChange::CreateList { list } => Some((&list._id, self.remote.create_board_list(list.board_id.clone(), &list.name, None).await?._id)),
+            OutboxChange::CreateCard { card } => Some((&card._id, self.remote.create_list_card(card.list_id.clone(), &card.name, None).await?._id)),
+            OutboxChange::UpdateCard { card, base } => {
+                let pushed_card = self.push_card(card, base.as_ref()).await?;
+                // A later edit still waiting in the outbox stays on the local copy until it is pushed as well
+                let local_card = self.local.get_card(card._id.clone()).await.ok();
+                if local_card.is_none_or(|local_card| local_card.local_revision <= card.local_revision) {
+                    self.local.update_card(&pushed_card).await?;
+                }
+                None
+            }
+            OutboxChange::AddComment { comment } => Some((
+                &comment._id,
+                self.remote.add_card_comment(comment.card_id.clone(), &comment.text, None).await?._id,
+            )),
+            OutboxChange::CreateChecklist { checklist } => Some((
+                &checklist._id,
+                self.remote.create_card_checklist(checklist.card_id.clone(), &checklist.name, None).await?._id,
+            )),
+            OutboxChange::CreateTask { task } => Some((
+                &task._id,
+                self.remote.create_checklist_task(task.checklist_id.clone(), &task.name, None).await?._id,
+            )),
+            OutboxChange::UpdateTask { card_id, task, base } => {
+                let pushed_task = self.push_checklist_task(card_id, task, base.as_ref()).await?;
+                let local_task = self.find_local_task(task).await;
+                if local_task.is_none_or(|local_task| local_task.local_revision <= task.local_revision) {
+                    self.local.update_checklist_task(card_id.clone(), &pushed_task).await?;
+                }
+                None
+            }
+        };
+
+        match created_ids {
+            Some((ID { local_id: Some(local_id), .. }, ID { trello_id: Some(trello_id), .. })) => {
+                self.local.set_trello_id(local_id, &trello_id).await?;
+                Ok(Some((local_id.clone(), trello_id)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /**
+     * Sends an edited card to Trello, merged with whatever changed there since `base` was read.
+     * Returns the card as it is on Trello now, with the local ids of `card`.
+     */
+    async fn push_card(&mut self, card: &Card, base: Option<&Card>) -> Result<Card, Box<dyn std::error::Error>> {
+        let mut merged_card = card.clone();
+        if let Some(base) = base {
+            let trello_card = self.remote.get_card(card._id.clone()).await?;
+            // Nothing happened to the card on Trello since it was read, so there is nothing to merge
+            let is_unchanged = trello_card.last_activity_instant_seconds != 0
+                && trello_card.last_activity_instant_seconds == base.last_activity_instant_seconds;
+            if !is_unchanged {
+                if !is_edited_since(card.local_revision, base.local_revision) {
+                    // Nothing was edited here, so Trello's card is taken as it is and cannot conflict
+                    let (trello_copy, _) = merge(base, base, &trello_card)?;
+                    return Ok(Card { local_revision: 0, ..trello_copy });
+                }
+                let (merged, conflicts) = merge(base, card, &trello_card)?;
+                self.record_conflict(ConflictedItem::Card { card: merged.clone() }, conflicts).await?;
+                merged_card = merged;
+            }
+        }
+
+        let trello_card = self.remote.update_card(&merged_card).await?;
+        merged_card.last_activity_instant_seconds = trello_card.last_activity_instant_seconds;
+        merged_card.local_revision = 0;
+        Ok(merged_card)
+    }
+
+    /**
+     * Same as `push_card` for a task. Trello does not track when a task changed, so every field is compared.
+     */
+    async fn push_checklist_task(&mut self, card_id: &ID, task: &CardChecklistTask, base: Option<&CardChecklistTask>) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
+        let mut merged_task = task.clone();
+        if let Some(base) = base {
+            let trello_tasks = self.remote.get_checklist_tasks(task.checklist_id.clone()).await?;
+            if let Some(trello_task) = trello_tasks.iter().find(|trello_task| trello_task._id.trello_id == task._id.trello_id) {
+                if !is_edited_since(task.local_revision, base.local_revision) {
+                    let (trello_copy, _) = merge(base, base, trello_task)?;
+                    return Ok(CardChecklistTask { local_revision: 0, ..trello_copy });
+                }
+                let (merged, conflicts) = merge(base, task, trello_task)?;
+                self.record_conflict(ConflictedItem::Task { card_id: card_id.clone(), task: merged.clone() }, conflicts).await?;
+                merged_task = merged;
+            }
+        }
+
+        self.remote.update_checklist_task(card_id.clone(), &merged_task).await?;
+        merged_task.local_revision = 0;
+        Ok(merged_task)
+    }
+
+    /**
+     * The local copy of `task`, if the local store has one.
+     */
+    async fn find_local_task(&self, task: &CardChecklistTask) -> Option<CardChecklistTask> {
+        self.local
+            .get_checklist_tasks(task.checklist_id.clone())
+            .await
+            .ok()
+            .and_then(|tasks| tasks.into_iter().find(|local_task| local_task._id == task._id))
+    }
+
+    /**
+     * Keeps the fields that conflicted in a merge until the user picks a side, together with the item as it was merged.
+     * A card or task has at most one conflict, later conflicts are added to it.
+     */
+    async fn record_conflict(&mut self, item: ConflictedItem, fields: Vec<FieldConflict>) -> Result<(), Box<dyn std::error::Error>> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        self.recorded_conflicts += fields.len();
+        let existing = self
+            .local
+            .get_conflicts()
+            .await?
+            .into_iter()
+            .find(|conflict| conflict.item.id().trello_id == item.id().trello_id);
+        let mut conflict = existing.unwrap_or(SyncConflict {
+            _id: ID { trello_id: None, local_id: new_local_id() },
+            item: item.clone(),
+            fields: vec![],
         });
 
-        dr
+        conflict.item = item;
+        for field in fields {
+            conflict.fields.retain(|existing_field| existing_field.field != field.field);
+            conflict.fields.push(field);
+        }
+        self.local.save_conflict(&conflict).await
+    }
+
+    /**
+     * How many fields conflicted with changes on Trello since this was last called.
+     */
+    pub fn take_recorded_conflicts(&mut self) -> usize {
+        std::mem::take(&mut self.recorded_conflicts)
+    }
+
+    pub async fn get_conflicts(&self) -> Result<Vec<SyncConflict>, Box<dyn std::error::Error>> {
+        self.local.get_conflicts().await
+    }
+
+    /**
+     * Picks the local or the Trello value of `fields` (every field if empty) for the conflict at `index` in `get_conflicts`.
+     * Once every field has a side the item is saved like any other edit and the conflict is removed.
+     * Returns the conflict with the fields that are left.
+     */
+    pub async fn resolve_conflict(&mut self, index: usize, use_local: bool, fields: &[String]) -> Result<SyncConflict, Box<dyn std::error::Error>> {
+        let mut conflict = match self.local.get_conflicts().await?.into_iter().nth(index) {
+            Some(conflict) => conflict,
+            None => return Err(Box::new(InvalidInputError { message: Some(format!("There is no conflict number {}", index + 1)) })),
+        };
+        for field in fields {
+            if !conflict.fields.iter().any(|conflicting| &conflicting.field == field) {
+                return Err(Box::new(InvalidInputError { message: Some(format!("The field '{}' is not in conflict", field)) }));
+            }
+        }
+
+        let (chosen, left): (Vec<FieldConflict>, Vec<FieldConflict>) = conflict
+            .fields
+            .into_iter()
+            .partition(|conflicting| fields.is_empty() || fields.contains(&conflicting.field));
+        conflict.fields = left;
+        for field in chosen {
+            let value = if use_local { field.local } else { field.remote };
+            conflict.item = match conflict.item {
+                ConflictedItem::Card { card } => ConflictedItem::Card { card: with_field(&card, &field.field, value)? },
+                ConflictedItem::Task { card_id, task } => ConflictedItem::Task { card_id, task: with_field(&task, &field.field, value)? },
+            };
+        }
+
+        if !conflict.fields.is_empty() {
+            self.local.save_conflict(&conflict).await?;
+            return Ok(conflict);
+        }
+
+        // Removed first, in case writing the item runs into a new conflict
+        self.local.remove_conflict(&conflict._id).await?;
+        match &conflict.item {
+            ConflictedItem::Card { card } => {
+                self.update_card(card).await?;
+            }
+            ConflictedItem::Task { card_id, task } => {
+                self.write_checklist_task(card_id.clone(), task).await?;
+            }
+        }
+        Ok(conflict)
+    }
+
+    /**
+     * Cards just read from Trello with the edits still waiting in the outbox on top, so reading a list does not
+     * hide them before they are pushed. Where Trello changed the same field the pending edit is shown.
+     */
+    async fn with_pending_card_edits(&self, mut cards: Vec<Card>) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        let mut edits: Vec<(Card, Option<Card>)> = vec![];
+        for entry in self.local.get_outbox().await.unwrap_or_default() {
+            if let OutboxChange::UpdateCard { card, base } = entry.change {
+                edits.push((card, base));
+            }
+        }
+
+        let applied_edits = apply_pending_edits(&mut cards, &edits)?;
+        for (card, applied_edit) in cards.iter_mut().zip(applied_edits) {
+            if applied_edit.is_some() {
+                *card = self.local.update_card(card).await?;
+            }
+        }
+        Ok(cards)
+    }
+
+    /**
+     * Same as `with_pending_card_edits` for the tasks of a checklist.
+     */
+    async fn with_pending_task_edits(&self, mut tasks: Vec<CardChecklistTask>) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        let mut card_ids: Vec<ID> = vec![];
+        let mut edits: Vec<(CardChecklistTask, Option<CardChecklistTask>)> = vec![];
+        for entry in self.local.get_outbox().await.unwrap_or_default() {
+            if let OutboxChange::UpdateTask { card_id, task, base } = entry.change {
+                card_ids.push(card_id);
+                edits.push((task, base));
+            }
+        }
+
+        let applied_edits = apply_pending_edits(&mut tasks, &edits)?;
+        for (task, applied_edit) in tasks.iter_mut().zip(applied_edits) {
+            if let Some(index) = applied_edit {
+                *task = self.local.update_checklist_task(card_ids[index].clone(), task).await?;
+            }
+        }
+        Ok(tasks)
+    }
+
+    /**
+     * Called after items were synced from Trello, to record when.
+     */
+    async fn mark_synced(&self, sync_key: &str) {
+        // Only used to tell how stale the local copy is, so failing to record it should not fail the read
+        let _ = self.local.set_last_synced(sync_key, Utc::now().timestamp()).await;
+    }
+
+    /**
+     * Called once a read or sync from Trello is done. With `"sync": { "purge_deleted": true }` in the config,
+     * items that are gone from Trello are deleted right away instead of being left as tombstones.
+     */
+    async fn purge_deleted_if_configured(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.purge_deleted {
+            self.local.purge_tombstones().await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Upgrades the data in the local store to the schema this version writes, see `DataStore::migrate`.
+     */
+    pub async fn migrate_local_store(&self, dry_run: bool) -> Result<MigrationReport, Box<dyn std::error::Error>> {
+        match self.local.migrate(dry_run).await {
+            Err(why) if why.is::<NotImplError>() => Err(Box::new(InvalidInputError {
+                message: Some(String::from("Only the MongoDB store keeps a schema version. The other local stores are upgraded when they are opened")),
+            })),
+            migrate_result => migrate_result,
+        }
+    }
+
+    /**
+     * Deletes the local copies of items that are gone from Trello, see `DataStore::purge_tombstones`.
+     */
+    pub async fn purge_tombstones(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        let purged = self.local.purge_tombstones().await?;
+        self.cache_boards.take();
+        self.invalidate_caches(true, true, true, true);
+        Ok(purged)
+    }
+
+    /**
+     * Brings the local copy of everything on the board called `name` up to date with Trello, so all of it can be read offline.
+     * The first sync of a board (or any with `full`) reads the whole board, later ones only what Trello's actions feed shows changed.
+     */
+    pub async fn sync_board(&mut self, name: &str, full: bool) -> Result<BoardSnapshot, Box<dyn std::error::Error>> {
+        let board = match self.sync_remote_boards().await?.into_iter().find(|board| board.name.eq_ignore_ascii_case(name)) {
+            Some(board) => board,
+            None => return Err(Box::new(InvalidInputError { message: Some(format!("No board named '{}' was found on Trello", name)) })),
+        };
+        let snapshot = self.sync_board_changes(&board, full).await?;
+        self.purge_deleted_if_configured().await?;
+        Ok(snapshot)
+    }
+
+    /**
+     * Same as `sync_board` for every board.
+     */
+    pub async fn sync_all_boards(&mut self, full: bool) -> Result<Vec<BoardSnapshot>, Box<dyn std::error::Error>> {
+        let mut snapshots: Vec<BoardSnapshot> = vec![];
+        for board in self.sync_remote_boards().await? {
+            snapshots.push(self.sync_board_changes(&board, full).await?);
+        }
+        self.purge_deleted_if_configured().await?;
+        Ok(snapshots)
+    }
+
+    async fn sync_remote_boards(&mut self) -> Result<Vec<Board>, Box<dyn std::error::Error>> {
+        let trello_boards = self.remote.get_all_boards().await?;
+        let synced_boards = self.local.sync_boards(trello_boards).await?;
+        self.mark_synced(&sync_key("boards", None)).await;
+        self.cache_boards = Some(synced_boards.clone());
+        Ok(synced_boards)
+    }
+
+    /**
+     * Syncs `board` from the actions Trello has had on it since the cursor recorded by the last sync. Without a cursor,
+     * with `full`, or when Trello has too many actions to list, the whole board is read instead. Returns what was read.
+     */
+    async fn sync_board_changes(&mut self, board: &Board, full: bool) -> Result<BoardSnapshot, Box<dyn std::error::Error>> {
+        let cursor_key = sync_key("actions", Some(&board._id));
+        let cursor = if full { None } else { self.local.get_sync_cursor(&cursor_key).await.ok().flatten() };
+
+        let actions = match cursor {
+            Some(cursor) => match self.remote.get_board_actions(board._id.clone(), &cursor).await {
+                Ok(actions) => actions,
+                Err(why) if why.is::<NotImplError>() => None,
+                Err(why) => return Err(why),
+            },
+            None => None,
+        };
+
+        // Like the sync times, failing to record the cursor only means the next sync reads the whole board
+        match actions {
+            Some(actions) => {
+                let snapshot = self.apply_board_actions(board, &actions).await?;
+                if let Some(newest_action_id) = actions.first().and_then(|action| action._id.trello_id.as_ref()) {
+                    let _ = self.local.set_sync_cursor(&cursor_key, newest_action_id).await;
+                }
+                Ok(snapshot)
+            }
+            None => {
+                // Taken before reading, with some leeway for clocks that differ, so actions made meanwhile are applied next time
+                let cursor = (Utc::now() - Duration::minutes(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+                let snapshot = self.sync_board_snapshot(board).await?;
+                let _ = self.local.set_sync_cursor(&cursor_key, &cursor).await;
+                Ok(snapshot)
+            }
+        }
+    }
+
+    /**
+     * Reads again the labels and lists of `board`, and the cards, comments, checklists and tasks `actions` changed,
+     * and syncs them to the local store. Returns what was read.
+     */
+    async fn apply_board_actions(&mut self, board: &Board, actions: &[BoardAction]) -> Result<BoardSnapshot, Box<dyn std::error::Error>> {
+        let board_id = board._id.clone();
+        // Two small requests that also pick up renamed and archived lists, which the feed is not needed for
+        let trello_labels = self.remote.get_all_board_labels(board_id.clone()).await?;
+        let trello_lists = self.remote.get_all_board_lists(board_id.clone()).await?;
+        let mut snapshot = BoardSnapshot {
+            board: board.clone(),
+            labels: self.local.sync_labels(board_id.clone(), trello_labels).await?,
+            lists: self.local.sync_lists(board_id.clone(), trello_lists).await?,
+            cards: vec![],
+            checklists: vec![],
+            tasks: vec![],
+            comments: vec![],
+        };
+        self.mark_synced(&sync_key("labels", Some(&board_id))).await;
+        self.mark_synced(&sync_key("lists", Some(&board_id))).await;
+
+        let mut changed = changed_parents(actions);
+        for card_id in &changed.listless_cards {
+            if let Ok(card) = self.local.get_card(card_id.clone()).await {
+                push_unique(&mut changed.card_lists, &card.list_id);
+            }
+        }
+
+        for list_id in &changed.card_lists {
+            // Lists that were archived or moved to another board are not synced with this one
+            if let Some(list) = find_by_trello_id(&snapshot.lists, list_id) {
+                let cards = self.sync_list_cards(list).await?;
+                snapshot.cards.extend(cards);
+            }
+        }
+
+        for card_id in &changed.comment_cards {
+            if let Some(card) = self.changed_card(&snapshot, &changed, card_id).await {
+                let comments = self.sync_card_comments(&card).await?;
+                snapshot.comments.extend(comments);
+            }
+        }
+
+        for card_id in &changed.checklist_cards {
+            if let Some(card) = self.changed_card(&snapshot, &changed, card_id).await {
+                let checklists = self.sync_card_checklists(&card).await?;
+                for checklist in &checklists {
+                    if !changed.task_checklists.iter().any(|(_, checklist_id)| checklist_id.trello_id == checklist._id.trello_id) {
+                        changed.task_checklists.push((card._id.clone(), checklist._id.clone()));
+                    }
+                }
+                snapshot.checklists.extend(checklists);
+            }
+        }
+
+        for (card_id, checklist_id) in &changed.task_checklists {
+            let card = match self.changed_card(&snapshot, &changed, card_id).await {
+                Some(card) => card,
+                None => continue,
+            };
+            let checklist = match find_by_trello_id(&snapshot.checklists, checklist_id) {
+                Some(checklist) => Some(checklist.clone()),
+                None => self.local.get_card_checklists(card._id.clone()).await?.into_iter().find(|checklist| checklist._id.trello_id == checklist_id.trello_id),
+            };
+            if let Some(checklist) = checklist {
+                let tasks = self.sync_checklist_tasks(&checklist).await?;
+                snapshot.tasks.extend(tasks);
+            }
+        }
+
+        self.invalidate_caches(true, true, true, true);
+        Ok(snapshot)
+    }
+
+    /**
+     * The local copy of a card an action changed, unless it is gone from the board: then its list was just synced
+     * without it, and Trello would not return its comments or checklists anyway.
+     */
+    async fn changed_card(&self, snapshot: &BoardSnapshot, changed: &ChangedParents, card_id: &ID) -> Option<Card> {
+        if let Some(card) = find_by_trello_id(&snapshot.cards, card_id) {
+            return Some(card.clone());
+        }
+
+        let card = self.local.get_card(card_id.clone()).await.ok()?;
+        if contains_trello_id(&changed.card_lists, &card.list_id) {
+            None
+        } else {
+            Some(card)
+        }
+    }
+
+    async fn sync_list_cards(&self, list: &BoardList) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        let trello_cards = self.remote.get_all_list_cards(list._id.clone()).await?;
+        let synced_cards = self.local.sync_cards(list._id.clone(), trello_cards).await?;
+        let synced_cards = self.with_pending_card_edits(synced_cards).await?;
+        self.mark_synced(&sync_key("cards", Some(&list._id))).await;
+        Ok(synced_cards)
+    }
+
+    async fn sync_card_comments(&self, card: &Card) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
+        let trello_comments = self.remote.get_card_comments(card._id.clone()).await?;
+        let synced_comments = self.local.sync_comments(card._id.clone(), trello_comments).await?;
+        self.mark_synced(&sync_key("comments", Some(&card._id))).await;
+        Ok(synced_comments)
+    }
+
+    async fn sync_card_checklists(&self, card: &Card) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
+        let trello_checklists = self.remote.get_card_checklists(card._id.clone()).await?;
+        let synced_checklists = self.local.sync_checklists(card._id.clone(), trello_checklists).await?;
+        self.mark_synced(&sync_key("checklists", Some(&card._id))).await;
+        Ok(synced_checklists)
+    }
+
+    async fn sync_checklist_tasks(&self, checklist: &CardChecklist) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        let trello_tasks = self.remote.get_checklist_tasks(checklist._id.clone()).await?;
+        let synced_tasks = self.local.sync_tasks(checklist._id.clone(), trello_tasks).await?;
+        let synced_tasks = self.with_pending_task_edits(synced_tasks).await?;
+        self.mark_synced(&sync_key("tasks", Some(&checklist._id))).await;
+        Ok(synced_tasks)
+    }
+
+    /**
+     * Writes a snapshot of `board` read from Trello through the `sync_*` methods of the local store, parent by parent,
+     * and returns it with the local ids filled in.
+     */
+    async fn sync_board_snapshot(&mut self, board: &Board) -> Result<BoardSnapshot, Box<dyn std::error::Error>> {
+        let trello_snapshot = self.remote.get_board_snapshot(board).await?;
+        let board_id = board._id.clone();
+
+        let mut snapshot = BoardSnapshot {
+            board: board.clone(),
+            labels: self.local.sync_labels(board_id.clone(), trello_snapshot.labels).await?,
+            lists: self.local.sync_lists(board_id.clone(), trello_snapshot.lists).await?,
+            cards: vec![],
+            checklists: vec![],
+            tasks: vec![],
+            comments: vec![],
+        };
+        self.mark_synced(&sync_key("labels", Some(&board_id))).await;
+        self.mark_synced(&sync_key("lists", Some(&board_id))).await;
+
+        // Items created offline and not pushed yet have nothing on Trello to sync
+        for list in snapshot.lists.iter().filter(|list| list._id.trello_id.is_some()) {
+            let list_cards = items_under(&trello_snapshot.cards, &list._id, |card| &card.list_id);
+            let synced_cards = self.local.sync_cards(list._id.clone(), list_cards).await?;
+            snapshot.cards.extend(self.with_pending_card_edits(synced_cards).await?);
+            self.mark_synced(&sync_key("cards", Some(&list._id))).await;
+        }
+
+        for card in snapshot.cards.iter().filter(|card| card._id.trello_id.is_some()) {
+            let card_checklists = items_under(&trello_snapshot.checklists, &card._id, |checklist| &checklist.card_id);
+            snapshot.checklists.extend(self.local.sync_checklists(card._id.clone(), card_checklists).await?);
+            self.mark_synced(&sync_key("checklists", Some(&card._id))).await;
+
+            let card_comments = items_under(&trello_snapshot.comments, &card._id, |comment| &comment.card_id);
+            snapshot.comments.extend(self.local.sync_comments(card._id.clone(), card_comments).await?);
+            self.mark_synced(&sync_key("comments", Some(&card._id))).await;
+        }
+
+        for checklist in snapshot.checklists.iter().filter(|checklist| checklist._id.trello_id.is_some()) {
+            let checklist_tasks = items_under(&trello_snapshot.tasks, &checklist._id, |task| &task.checklist_id);
+            let synced_tasks = self.local.sync_tasks(checklist._id.clone(), checklist_tasks).await?;
+            snapshot.tasks.extend(self.with_pending_task_edits(synced_tasks).await?);
+            self.mark_synced(&sync_key("tasks", Some(&checklist._id))).await;
+        }
+
+        self.invalidate_caches(true, true, true, true);
+        Ok(snapshot)
+    }
+
+    async fn mark_read_locally(&mut self, sync_key: &str) {
+        let last_synced = self.local.get_last_synced(sync_key).await.ok().flatten();
+        self.local_read_last_synced = Some(last_synced.unwrap_or(0));
+    }
+
+    fn remember_cache_staleness(&mut self, cache: &'static str) {
+        self.cache_read_last_synced.insert(cache, self.local_read_last_synced);
+    }
+
+    /**
+     * When the last read could not reach Trello and was served from the local store, the time that
+     * data was last synced from Trello (0 if it never was). `None` when the data came from Trello.
+     */
+    pub fn local_read_last_synced(&self) -> Option<i64> {
+        self.local_read_last_synced
     }
 
     fn invalidate_caches(&mut self, boardlists: bool, cards: bool, checklists: bool, labels: bool) {
@@ -56,6 +722,7 @@ impl DataRepository {
 
         if checklists {
             self.cache_checklists.take();
+            self.cache_tasks.take();
         }
 
         if labels {
@@ -63,28 +730,86 @@ impl DataRepository {
         }
     }
 
+    /**
+     * Clears the active board, list, card and/or checklist along with the caches that belonged to them.
+     */
+    pub fn deselect(&mut self, board: bool, boardlist: bool, card: bool, checklist: bool) {
+        if board {
+            self.active_board.take();
+        }
+
+        if boardlist {
+            self.active_boardlist.take();
+        }
+
+        if card {
+            self.active_card.take();
+        }
+
+        if checklist {
+            self.active_checklist.take();
+            self.cache_tasks.take();
+        }
+
+        self.invalidate_caches(board, board || boardlist, board || boardlist || card, board);
+    }
+
+    /**
+     * Makes these the active board, list, card and checklist again, e.g. to undo a selection that failed part way.
+     * The caches that belonged to the items active before are dropped.
+     */
+    pub fn restore_selection(&mut self, board: Option<Board>, boardlist: Option<BoardList>, card: Option<Card>, checklist: Option<CardChecklist>) {
+        self.active_board = board;
+        self.active_boardlist = boardlist;
+        self.active_card = card;
+        self.active_checklist = checklist;
+        self.invalidate_caches(true, true, true, true);
+    }
+
+    pub fn get_cached_names(&self) -> CachedNames {
+        fn names<T>(cache: &Option<Vec<T>>, name: fn(&T) -> &String) -> Option<Vec<String>> {
+            cache.as_ref().map(|items| items.iter().map(|item| name(item).clone()).collect())
+        }
+
+        CachedNames {
+            boards: names(&self.cache_boards, |board| &board.name),
+            lists: names(&self.cache_boardlists, |list| &list.name),
+            cards: names(&self.cache_cards, |card| &card.name),
+            checklists: names(&self.cache_checklists, |checklist| &checklist.name),
+            labels: names(&self.cache_labels, |label| &label.name),
+            tasks: names(&self.cache_tasks, |task| &task.name),
+        }
+    }
+
     pub async fn get_all_boards(&mut self) -> Result<Vec<Board>, Box<dyn std::error::Error>> {
-        let trello_boards_result = TrelloDataStore::get_all_boards().await;
+        self.local_read_last_synced = None;
+        let trello_boards_result = self.remote.get_all_boards().await;
 
         match trello_boards_result {
             Ok(trello_boards) => {
-                let synced_boards = MongoDataStore::sync_boards(trello_boards).await?;
+                let synced_boards = self.local.sync_boards(trello_boards).await?;
+                self.mark_synced(&sync_key("boards", None)).await;
+                self.purge_deleted_if_configured().await?;
                 self.cache_boards = Some(synced_boards.clone());
                 Ok(synced_boards)
             }
 
+            // Trello answered and refused, e.g. a revoked token, which the local copy must not hide
+            Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => Err(trello_why),
             Err(trello_why) => {
-                let mongo_boards_result = MongoDataStore::get_all_boards().await;
+                let mongo_boards_result = self.local.get_all_boards().await;
                 match mongo_boards_result {
                     Ok(mongo_boards) => {
+                        self.mark_read_locally(&sync_key("boards", None)).await;
                         self.cache_boards = Some(mongo_boards.clone());
                         Ok(mongo_boards)
                     }
 
-                    Err(mongo_why) => {
+                    Err(_mongo_why) => {
                         if self.cache_boards.is_none() {
                             Err(trello_why)
                         } else {
+                            self.mark_read_locally(&sync_key("boards", None)).await;
                             Ok(self.cache_boards.clone().unwrap())
                         }
                     }
@@ -94,23 +819,29 @@ impl DataRepository {
     }
 
     pub async fn create_board(&mut self, name: &str) -> Result<Board, Box<dyn std::error::Error>> {
-        let trello_board_result = TrelloDataStore::create_board(name, None).await;
-        match trello_board_result {
-            Ok(trello_board) => {
-                let trello_id: String = trello_board._id.trello_id.unwrap();
-                let mongo_board = MongoDataStore::create_board(name, Some(trello_id)).await?;
-                if self.cache_boards.is_some() {
-                    self.cache_boards
-                        .as_mut()
-                        .unwrap()
-                        .push(mongo_board.clone());
-                }
-                self.invalidate_caches(true, true, true, true);
-                Ok(mongo_board)
+        let mut trello_id: Option<String> = None;
+        if self.can_write_remote().await {
+            match self.remote.create_board(name, None).await {
+                Ok(trello_board) => trello_id = trello_board._id.trello_id,
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
             }
+        }
+
+        let is_offline = trello_id.is_none();
+        let mongo_board = self.local.create_board(name, trello_id).await?;
+        if is_offline {
+            self.queue_change(OutboxChange::CreateBoard { board: mongo_board.clone() }).await?;
+        }
 
-            Err(trello_why) => Err(trello_why),
+        if self.cache_boards.is_some() {
+            self.cache_boards
+                .as_mut()
+                .unwrap()
+                .push(mongo_board.clone());
         }
+        self.invalidate_caches(true, true, true, true);
+        Ok(mongo_board)
     }
 
     pub async fn select_board(
@@ -143,6 +874,7 @@ impl DataRepository {
         &mut self,
         board: Option<Board>,
     ) -> Result<Vec<CardLabel>, Box<dyn std::error::Error>> {
+        self.local_read_last_synced = None;
         let board_id: ID;
 
         if board.is_none() {
@@ -154,6 +886,7 @@ impl DataRepository {
                 }
             } else {
                 let labels = self.cache_labels.clone().unwrap();
+                self.local_read_last_synced = self.cache_read_last_synced.get("labels").copied().flatten();
                 return Ok(labels);
             }
         } else {
@@ -161,16 +894,35 @@ impl DataRepository {
             self.invalidate_caches(true, true, true, true);
         }
 
-        let labels_result = TrelloDataStore::get_all_board_labels(board_id.clone()).await;
+        if board_id.trello_id.is_none() {
+            // Created offline and not pushed yet, so only the local store knows about it
+            let local_labels = self.local.get_all_board_labels(board_id).await?;
+            self.cache_labels.replace(local_labels.clone());
+            self.remember_cache_staleness("labels");
+            return Ok(local_labels);
+        }
+
+        let labels_result = self.remote.get_all_board_labels(board_id.clone()).await;
         match labels_result {
             Ok(trello_labels) => {
-                let synced_labels = MongoDataStore::sync_labels(board_id, trello_labels).await?;
+                let synced_labels = self.local.sync_labels(board_id.clone(), trello_labels).await?;
+                self.mark_synced(&sync_key("labels", Some(&board_id))).await;
+                self.purge_deleted_if_configured().await?;
                 self.cache_labels.replace(synced_labels.clone());
+                self.remember_cache_staleness("labels");
                 Ok(synced_labels)
             }
 
-            Err(why) => {
-                Err(why)
+            Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => Err(trello_why),
+            Err(trello_why) => {
+                let local_labels = match self.local.get_all_board_labels(board_id.clone()).await {
+                    Ok(local_labels) => local_labels,
+                    Err(_) => return Err(trello_why),
+                };
+                self.mark_read_locally(&sync_key("labels", Some(&board_id))).await;
+                self.cache_labels.replace(local_labels.clone());
+                self.remember_cache_staleness("labels");
+                Ok(local_labels)
             }
         }
     }
@@ -208,8 +960,20 @@ impl DataRepository {
         if label_id.is_none() {
             return Err(Box::new(InvalidInputError { message: Some(String::from("Could not find the given label name for the chosen board"))}));
         } else {
-            MongoDataStore::delete_board_label(label_id.clone().unwrap()).await?;
-            return TrelloDataStore::delete_board_label(label_id.unwrap()).await;
+            let label_id = label_id.unwrap();
+            let mut is_offline = true;
+            if self.can_write_remote().await {
+                match self.remote.delete_board_label(label_id.clone()).await {
+                    Ok(_) => is_offline = false,
+                    Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                    Err(_) => {}
+                }
+            }
+
+            if is_offline {
+                self.queue_change(OutboxChange::DeleteLabel { label_id: label_id.clone() }).await?;
+            }
+            return self.local.delete_board_label(label_id).await;
         }
     }
 
@@ -246,8 +1010,21 @@ impl DataRepository {
         if label_id.is_none() {
             return Err(Box::new(InvalidInputError { message: Some(String::from("Could not find the given label for the chosen board"))}));
         } else {
-            TrelloDataStore::update_board_label(label_id.clone().unwrap(), name, color).await?;
-            return MongoDataStore::update_board_label(label_id.unwrap(), name, color).await;
+            let label_id = label_id.unwrap();
+            let mut is_offline = true;
+            if self.can_write_remote().await {
+                match self.remote.update_board_label(label_id.clone(), name, color).await {
+                    Ok(_) => is_offline = false,
+                    Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                    Err(_) => {}
+                }
+            }
+
+            if is_offline {
+                let change = OutboxChange::UpdateLabel { label_id: label_id.clone(), name: name.to_string(), color: color.to_string() };
+                self.queue_change(change).await?;
+            }
+            return self.local.update_board_label(label_id, name, color).await;
         }
     }
 
@@ -265,14 +1042,28 @@ impl DataRepository {
             };
         }
         self.invalidate_caches(false, true, false, true);
-        let trello_label = TrelloDataStore::create_board_label(board_id.clone(), name, color, None).await?;
-        return MongoDataStore::create_board_label(board_id, name, color, trello_label._id.trello_id).await;
+        let mut trello_id: Option<String> = None;
+        if self.can_write_remote().await {
+            match self.remote.create_board_label(board_id.clone(), name, color, None).await {
+                Ok(trello_label) => trello_id = trello_label._id.trello_id,
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
+            }
+        }
+
+        let is_offline = trello_id.is_none();
+        let label = self.local.create_board_label(board_id, name, color, trello_id).await?;
+        if is_offline {
+            self.queue_change(OutboxChange::CreateLabel { label: label.clone() }).await?;
+        }
+        Ok(label)
     }
 
     pub async fn get_all_board_lists(
         &mut self,
         board: Option<Board>,
     ) -> Result<Vec<BoardList>, Box<dyn std::error::Error>> {
+        self.local_read_last_synced = None;
         let board_id: ID;
 
         if board.is_none() {
@@ -284,6 +1075,7 @@ impl DataRepository {
                 }
             } else {
                 let lists = self.cache_boardlists.clone().unwrap();
+                self.local_read_last_synced = self.cache_read_last_synced.get("lists").copied().flatten();
                 return Ok(lists);
             }
         } else {
@@ -291,16 +1083,35 @@ impl DataRepository {
             self.invalidate_caches(true, true, true, true);
         }
 
-        let lists_result = TrelloDataStore::get_all_board_lists(board_id.clone()).await;
+        if board_id.trello_id.is_none() {
+            // Created offline and not pushed yet, so only the local store knows about it
+            let local_lists = self.local.get_all_board_lists(board_id).await?;
+            self.cache_boardlists.replace(local_lists.clone());
+            self.remember_cache_staleness("lists");
+            return Ok(local_lists);
+        }
+
+        let lists_result = self.remote.get_all_board_lists(board_id.clone()).await;
         match lists_result {
             Ok(trello_lists) => {
-                let synced_lists = MongoDataStore::sync_lists(board_id, trello_lists).await?;
+                let synced_lists = self.local.sync_lists(board_id.clone(), trello_lists).await?;
+                self.mark_synced(&sync_key("lists", Some(&board_id))).await;
+                self.purge_deleted_if_configured().await?;
                 self.cache_boardlists.replace(synced_lists.clone());
+                self.remember_cache_staleness("lists");
                 Ok(synced_lists)
             }
 
-            Err(why) => {
-                Err(why)
+            Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => Err(trello_why),
+            Err(trello_why) => {
+                let local_lists = match self.local.get_all_board_lists(board_id.clone()).await {
+                    Ok(local_lists) => local_lists,
+                    Err(_) => return Err(trello_why),
+                };
+                self.mark_read_locally(&sync_key("lists", Some(&board_id))).await;
+                self.cache_boardlists.replace(local_lists.clone());
+                self.remember_cache_staleness("lists");
+                Ok(local_lists)
             }
         }
     }
@@ -319,8 +1130,21 @@ impl DataRepository {
             };
         }
         self.invalidate_caches(false, true, true, true);
-        let trello_list = TrelloDataStore::create_board_list(board_id.clone(), name, None).await?;
-        return MongoDataStore::create_board_list(board_id, name, trello_list._id.trello_id).await;
+        let mut trello_id: Option<String> = None;
+        if self.can_write_remote().await {
+            match self.remote.create_board_list(board_id.clone(), name, None).await {
+                Ok(trello_list) => trello_id = trello_list._id.trello_id,
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
+            }
+        }
+
+        let is_offline = trello_id.is_none();
+        let list = self.local.create_board_list(board_id, name, trello_id).await?;
+        if is_offline {
+            self.queue_change(OutboxChange::CreateList { list: list.clone() }).await?;
+        }
+        Ok(list)
     }
 
     pub async fn select_board_list(
@@ -354,6 +1178,7 @@ impl DataRepository {
         &mut self,
         list: Option<BoardList>,
     ) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        self.local_read_last_synced = None;
         let list_id: ID;
 
         if list.is_none() {
@@ -365,6 +1190,7 @@ impl DataRepository {
                 }
             } else {
                 let cards = self.cache_cards.clone().unwrap();
+                self.local_read_last_synced = self.cache_read_last_synced.get("cards").copied().flatten();
                 return Ok(cards);
             }
         } else {
@@ -372,16 +1198,36 @@ impl DataRepository {
             self.invalidate_caches(true, true, true, true);
         }
 
-        let cards_result = TrelloDataStore::get_all_list_cards(list_id.clone()).await;
+        if list_id.trello_id.is_none() {
+            // Created offline and not pushed yet, so only the local store knows about it
+            let local_cards = self.local.get_all_list_cards(list_id).await?;
+            self.cache_cards.replace(local_cards.clone());
+            self.remember_cache_staleness("cards");
+            return Ok(local_cards);
+        }
+
+        let cards_result = self.remote.get_all_list_cards(list_id.clone()).await;
         match cards_result {
             Ok(trello_cards) => {
-                let synced_cards = MongoDataStore::sync_cards(list_id, trello_cards).await?;
+                let synced_cards = self.local.sync_cards(list_id.clone(), trello_cards).await?;
+                let synced_cards = self.with_pending_card_edits(synced_cards).await?;
+                self.mark_synced(&sync_key("cards", Some(&list_id))).await;
+                self.purge_deleted_if_configured().await?;
                 self.cache_cards.replace(synced_cards.clone());
+                self.remember_cache_staleness("cards");
                 Ok(synced_cards)
             }
 
-            Err(why) => {
-                Err(why)
+            Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => Err(trello_why),
+            Err(trello_why) => {
+                let local_cards = match self.local.get_all_list_cards(list_id.clone()).await {
+                    Ok(local_cards) => local_cards,
+                    Err(_) => return Err(trello_why),
+                };
+                self.mark_read_locally(&sync_key("cards", Some(&list_id))).await;
+                self.cache_cards.replace(local_cards.clone());
+                self.remember_cache_staleness("cards");
+                Ok(local_cards)
             }
         }
     }
@@ -400,8 +1246,21 @@ impl DataRepository {
             };
         }
         self.invalidate_caches(false, true, true, false);
-        let trello_card = TrelloDataStore::create_list_card(list_id.clone(), name, None).await?;
-        return MongoDataStore::create_list_card(list_id, name, trello_card._id.trello_id).await;
+        let mut trello_id: Option<String> = None;
+        if self.can_write_remote().await {
+            match self.remote.create_list_card(list_id.clone(), name, None).await {
+                Ok(trello_card) => trello_id = trello_card._id.trello_id,
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
+            }
+        }
+
+        let is_offline = trello_id.is_none();
+        let card = self.local.create_list_card(list_id, name, trello_id).await?;
+        if is_offline {
+            self.queue_change(OutboxChange::CreateCard { card: card.clone() }).await?;
+        }
+        Ok(card)
     }
 
     pub async fn select_list_card(
@@ -434,28 +1293,65 @@ impl DataRepository {
 
     pub async fn update_card(&mut self, card: &Card) -> Result<Card, Box<dyn std::error::Error>> {
         self.invalidate_caches(false, true, false, false);
-        TrelloDataStore::update_card(card).await?;
-        MongoDataStore::update_card(card).await
+        // The card as it was last read, to tell the fields edited here from the ones changed on Trello since
+        let base = self.local.get_card(card._id.clone()).await.ok();
+        let card = &Card { local_revision: base.as_ref().map_or(0, |base| base.local_revision) + 1, ..card.clone() };
+        let mut updated_card: Option<Card> = None;
+        if self.can_write_remote().await {
+            match self.push_card(card, base.as_ref()).await {
+                Ok(pushed_card) => updated_card = Some(pushed_card),
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
+            }
+        }
+
+        match updated_card {
+            Some(pushed_card) => self.local.update_card(&pushed_card).await,
+            None => {
+                self.queue_change(OutboxChange::UpdateCard { card: card.clone(), base }).await?;
+                self.local.update_card(card).await
+            }
+        }
     }
 
     pub async fn get_card_comments(&mut self, card: Option<Card>) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
+        self.local_read_last_synced = None;
         let card_id: ID;
-        let trello_comments: Vec<CardComment>;
-        
+
         if card.is_none() {
             if self.active_card.is_none() {
                 return Err(Box::new(InvalidInputError { message: Some(String::from("No card has been selected or provided. Unable to infer which card's comments to get"))}));
             } else {
                 card_id = self.active_card.clone().unwrap()._id;
-                trello_comments = TrelloDataStore::get_card_comments(card_id.clone()).await?;
             }
         } else {
             card_id = card.clone().unwrap()._id;
             self.invalidate_caches(true, true, true, true);
-            trello_comments = TrelloDataStore::get_card_comments(card_id.clone()).await?;
         }
 
-        MongoDataStore::sync_comments(card_id, trello_comments).await
+        if card_id.trello_id.is_none() {
+            // Created offline and not pushed yet, so only the local store knows about it
+            return self.local.get_card_comments(card_id).await;
+        }
+
+        match self.remote.get_card_comments(card_id.clone()).await {
+            Ok(trello_comments) => {
+                let synced_comments = self.local.sync_comments(card_id.clone(), trello_comments).await?;
+                self.mark_synced(&sync_key("comments", Some(&card_id))).await;
+                self.purge_deleted_if_configured().await?;
+                Ok(synced_comments)
+            }
+
+            Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => Err(trello_why),
+            Err(trello_why) => {
+                let local_comments = match self.local.get_card_comments(card_id.clone()).await {
+                    Ok(local_comments) => local_comments,
+                    Err(_) => return Err(trello_why),
+                };
+                self.mark_read_locally(&sync_key("comments", Some(&card_id))).await;
+                Ok(local_comments)
+            }
+        }
     }
 
     pub async fn add_card_comment(&mut self, card: Option<Card>, text: &str) -> Result<CardComment, Box<dyn std::error::Error>> {
@@ -466,17 +1362,31 @@ impl DataRepository {
                 return Err(Box::new(InvalidInputError { message: Some(String::from("No card has been selected or provided. Unable to infer which card to add a comment to"))}));
             } else {
                 card_id = self.active_card.clone().unwrap()._id;
-                return TrelloDataStore::add_card_comment(card_id, text, None).await;
             }
         } else {
             card_id = card.clone().unwrap()._id;
             self.invalidate_caches(true, true, true, true);
-            let trello_comment = TrelloDataStore::add_card_comment(card_id.clone(), text, None).await?;
-            return MongoDataStore::add_card_comment(card_id, text, trello_comment._id.trello_id).await;
         }
+
+        let mut trello_id: Option<String> = None;
+        if self.can_write_remote().await {
+            match self.remote.add_card_comment(card_id.clone(), text, None).await {
+                Ok(trello_comment) => trello_id = trello_comment._id.trello_id,
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
+            }
+        }
+
+        let is_offline = trello_id.is_none();
+        let comment = self.local.add_card_comment(card_id, text, trello_id).await?;
+        if is_offline {
+            self.queue_change(OutboxChange::AddComment { comment: comment.clone() }).await?;
+        }
+        Ok(comment)
     }
 
     pub async fn get_card_checklists(&mut self, card: Option<Card>) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
+        self.local_read_last_synced = None;
         let card_id: ID;
 
         if card.is_none() {
@@ -488,6 +1398,7 @@ impl DataRepository {
                 }
             } else {
                 let checklists = self.cache_checklists.clone().unwrap();
+                self.local_read_last_synced = self.cache_read_last_synced.get("checklists").copied().flatten();
                 return Ok(checklists);
             }
         } else {
@@ -495,16 +1406,35 @@ impl DataRepository {
             self.invalidate_caches(true, true, true, true);
         }
 
-        let checklists_result = TrelloDataStore::get_card_checklists(card_id.clone()).await;
+        if card_id.trello_id.is_none() {
+            // Created offline and not pushed yet, so only the local store knows about it
+            let local_checklists = self.local.get_card_checklists(card_id).await?;
+            self.cache_checklists.replace(local_checklists.clone());
+            self.remember_cache_staleness("checklists");
+            return Ok(local_checklists);
+        }
+
+        let checklists_result = self.remote.get_card_checklists(card_id.clone()).await;
         match checklists_result {
             Ok(trello_checklists) => {
-                let synced_checklists = MongoDataStore::sync_checklists(card_id, trello_checklists).await?;
+                let synced_checklists = self.local.sync_checklists(card_id.clone(), trello_checklists).await?;
+                self.mark_synced(&sync_key("checklists", Some(&card_id))).await;
+                self.purge_deleted_if_configured().await?;
                 self.cache_checklists.replace(synced_checklists.clone());
+                self.remember_cache_staleness("checklists");
                 Ok(synced_checklists)
             }
 
-            Err(why) => {
-                Err(why)
+            Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => Err(trello_why),
+            Err(trello_why) => {
+                let local_checklists = match self.local.get_card_checklists(card_id.clone()).await {
+                    Ok(local_checklists) => local_checklists,
+                    Err(_) => return Err(trello_why),
+                };
+                self.mark_read_locally(&sync_key("checklists", Some(&card_id))).await;
+                self.cache_checklists.replace(local_checklists.clone());
+                self.remember_cache_staleness("checklists");
+                Ok(local_checklists)
             }
         }
     }
@@ -523,8 +1453,21 @@ impl DataRepository {
             };
         }
         self.invalidate_caches(false, false, true, false);
-        let trello_checklist = TrelloDataStore::create_card_checklist(card_id.clone(), name, None).await?;
-        return MongoDataStore::create_card_checklist(card_id, name, trello_checklist._id.trello_id).await;
+        let mut trello_id: Option<String> = None;
+        if self.can_write_remote().await {
+            match self.remote.create_card_checklist(card_id.clone(), name, None).await {
+                Ok(trello_checklist) => trello_id = trello_checklist._id.trello_id,
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
+            }
+        }
+
+        let is_offline = trello_id.is_none();
+        let checklist = self.local.create_card_checklist(card_id, name, trello_id).await?;
+        if is_offline {
+            self.queue_change(OutboxChange::CreateChecklist { checklist: checklist.clone() }).await?;
+        }
+        Ok(checklist)
     }
 
     pub async fn select_card_checklist(&mut self, card: Option<Card>, name: &str) -> Result<Option<CardChecklist>, Box<dyn std::error::Error>> {
@@ -552,6 +1495,7 @@ impl DataRepository {
     }
 
     pub async fn get_checklist_tasks(&mut self, checklist: Option<CardChecklist>) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        self.local_read_last_synced = None;
         let checklist_id: ID;
 
         if checklist.is_none() {
@@ -565,8 +1509,34 @@ impl DataRepository {
             self.invalidate_caches(true, true, true, true);
         }
 
-        let trello_tasks = TrelloDataStore::get_checklist_tasks(checklist_id.clone()).await?;
-        MongoDataStore::sync_tasks(checklist_id, trello_tasks).await
+        if checklist_id.trello_id.is_none() {
+            // Created offline and not pushed yet, so only the local store knows about it
+            let local_tasks = self.local.get_checklist_tasks(checklist_id).await?;
+            self.cache_tasks.replace(local_tasks.clone());
+            return Ok(local_tasks);
+        }
+
+        match self.remote.get_checklist_tasks(checklist_id.clone()).await {
+            Ok(trello_tasks) => {
+                let synced_tasks = self.local.sync_tasks(checklist_id.clone(), trello_tasks).await?;
+                let synced_tasks = self.with_pending_task_edits(synced_tasks).await?;
+                self.mark_synced(&sync_key("tasks", Some(&checklist_id))).await;
+                self.purge_deleted_if_configured().await?;
+                self.cache_tasks.replace(synced_tasks.clone());
+                Ok(synced_tasks)
+            }
+
+            Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => Err(trello_why),
+            Err(trello_why) => {
+                let local_tasks = match self.local.get_checklist_tasks(checklist_id.clone()).await {
+                    Ok(local_tasks) => local_tasks,
+                    Err(_) => return Err(trello_why),
+                };
+                self.mark_read_locally(&sync_key("tasks", Some(&checklist_id))).await;
+                self.cache_tasks.replace(local_tasks.clone());
+                Ok(local_tasks)
+            }
+        }
     }
 
     pub async fn create_checklist_task(&mut self, checklist: Option<CardChecklist>, name: &str) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
@@ -582,8 +1552,21 @@ impl DataRepository {
                 self.active_checklist.clone().unwrap()._id
             };
         }
-        let trello_task = TrelloDataStore::create_checklist_task(checklist_id.clone(), name, None).await?;
-        return MongoDataStore::create_checklist_task(checklist_id, name, trello_task._id.trello_id).await;
+        let mut trello_id: Option<String> = None;
+        if self.can_write_remote().await {
+            match self.remote.create_checklist_task(checklist_id.clone(), name, None).await {
+                Ok(trello_task) => trello_id = trello_task._id.trello_id,
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
+            }
+        }
+
+        let is_offline = trello_id.is_none();
+        let task = self.local.create_checklist_task(checklist_id, name, trello_id).await?;
+        if is_offline {
+            self.queue_change(OutboxChange::CreateTask { task: task.clone() }).await?;
+        }
+        Ok(task)
     }
 
     pub async fn update_checklist_task(&mut self, card: Option<Card>, task: CardChecklistTask) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
@@ -599,35 +1582,697 @@ impl DataRepository {
                 self.active_card.clone().unwrap()._id
             };
         }
-        TrelloDataStore::update_checklist_task(card_id.clone(), &task).await?;
-        MongoDataStore::update_checklist_task(card_id, &task).await
+        self.write_checklist_task(card_id, &task).await
+    }
+
+    async fn write_checklist_task(&mut self, card_id: ID, task: &CardChecklistTask) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
+        let base = self.find_local_task(task).await;
+        let task = &CardChecklistTask { local_revision: base.as_ref().map_or(0, |base| base.local_revision) + 1, ..task.clone() };
+        let mut updated_task: Option<CardChecklistTask> = None;
+        if self.can_write_remote().await {
+            match self.push_checklist_task(&card_id, task, base.as_ref()).await {
+                Ok(pushed_task) => updated_task = Some(pushed_task),
+                Err(trello_why) if !is_remote_unavailable(trello_why.as_ref()) => return Err(trello_why),
+                Err(_) => {}
+            }
+        }
+
+        match updated_task {
+            Some(pushed_task) => self.local.update_checklist_task(card_id, &pushed_task).await,
+            None => {
+                self.queue_change(OutboxChange::UpdateTask { card_id: card_id.clone(), task: task.clone(), base }).await?;
+                self.local.update_checklist_task(card_id, task).await
+            }
+        }
+    }
+}
+
+/**
+ * Whether an edit at `revision` has changes of its own over the copy at `base_revision` it was made on.
+ * Edits queued before items kept a revision have 0 and are taken as edited.
+ */
+fn is_edited_since(revision: u64, base_revision: u64) -> bool {
+    revision == 0 || revision > base_revision
+}
+
+/**
+ * Whether `why` means Trello could not be reached at all, rather than that it rejected the request.
+ */
+fn is_remote_unavailable(why: &(dyn std::error::Error + 'static)) -> bool {
+    if why.is::<RemoteUnavailableError>() {
+        return true;
+    }
+
+    match why.downcast_ref::<reqwest::Error>() {
+        Some(why) => why.is_connect() || why.is_timeout(),
+        None => false,
+    }
+}
+
+/**
+ * Key the local store records the last sync time under, e.g. `lists/<board id>` for the lists of a board.
+ */
+fn sync_key(kind: &str, parent_id: Option<&ID>) -> String {
+    match parent_id.and_then(|id| id.local_id.as_ref().or(id.trello_id.as_ref())) {
+        Some(parent_id) => format!("{}/{}", kind, parent_id),
+        None => kind.to_string(),
+    }
+}
+
+/**
+ * Applies pending `edits` (each an edited item and the item it was edited from) to the matching `items` read from Trello.
+ * Returns, for every item, the index of the last edit applied to it if any.
+ */
+fn apply_pending_edits<T: StoredItem>(items: &mut [T], edits: &[(T, Option<T>)]) -> Result<Vec<Option<usize>>, Box<dyn std::error::Error>> {
+    let mut applied_edits: Vec<Option<usize>> = vec![];
+    for item in items.iter_mut() {
+        let mut applied_edit: Option<usize> = None;
+        for (index, (edited, base)) in edits.iter().enumerate() {
+            if edited.id().trello_id.is_none() || edited.id().trello_id != item.id().trello_id {
+                continue;
+            }
+
+            *item = match base {
+                // The edit goes in as the remote side, so it wins where both changed a field
+                Some(base) => merge(base, item, edited)?.0,
+                None => edited.clone(),
+            };
+            applied_edit = Some(index);
+        }
+        applied_edits.push(applied_edit);
+    }
+    Ok(applied_edits)
+}
+
+/**
+ * Action types that change which cards are in a list or what is on a card, so the card's list is read again.
+ */
+const CARD_ACTION_TYPES: [&str; 11] = [
+    "createCard", "copyCard", "convertToCardFromCheckItem", "moveCardToBoard", "moveCardFromBoard", "updateCard", "deleteCard",
+    "addLabelToCard", "removeLabelFromCard", "addChecklistToCard", "removeChecklistFromCard",
+];
+const COMMENT_ACTION_TYPES: [&str; 5] = ["commentCard", "updateComment", "deleteComment", "copyCommentCard", "copyCard"];
+const CHECKLIST_ACTION_TYPES: [&str; 4] = ["addChecklistToCard", "removeChecklistFromCard", "updateChecklist", "copyCard"];
+const TASK_ACTION_TYPES: [&str; 4] = ["createCheckItem", "updateCheckItem", "updateCheckItemStateOnCard", "deleteCheckItem"];
+
+/**
+ * What has to be read again from Trello to apply some actions, by Trello id. The tasks of checklists are
+ * listed with the card the checklist is on.
+ */
+#[derive(Default)]
+struct ChangedParents {
+    card_lists: Vec<ID>,
+    listless_cards: Vec<ID>,
+    comment_cards: Vec<ID>,
+    checklist_cards: Vec<ID>,
+    task_checklists: Vec<(ID, ID)>,
+}
+
+fn changed_parents(actions: &[BoardAction]) -> ChangedParents {
+    let mut changed = ChangedParents::default();
+    for action in actions {
+        let action_type = action.action_type.as_str();
+        let card_id = match &action.card_id {
+            Some(card_id) => card_id,
+            None => continue,
+        };
+
+        if CARD_ACTION_TYPES.contains(&action_type) {
+            if action.list_ids.is_empty() {
+                push_unique(&mut changed.listless_cards, card_id);
+            }
+            for list_id in &action.list_ids {
+                push_unique(&mut changed.card_lists, list_id);
+            }
+        }
+        if COMMENT_ACTION_TYPES.contains(&action_type) {
+            push_unique(&mut changed.comment_cards, card_id);
+        }
+        if CHECKLIST_ACTION_TYPES.contains(&action_type) {
+            push_unique(&mut changed.checklist_cards, card_id);
+        }
+        if let (true, Some(checklist_id)) = (TASK_ACTION_TYPES.contains(&action_type), &action.checklist_id) {
+            if !changed.task_checklists.iter().any(|(_, existing)| existing.trello_id == checklist_id.trello_id) {
+                changed.task_checklists.push((card_id.clone(), checklist_id.clone()));
+            }
+        }
+    }
+    changed
+}
+
+fn push_unique(ids: &mut Vec<ID>, id: &ID) {
+    if id.trello_id.is_some() && !contains_trello_id(ids, id) {
+        ids.push(id.clone());
     }
 }
 
+fn contains_trello_id(ids: &[ID], id: &ID) -> bool {
+    ids.iter().any(|existing| existing.trello_id.is_some() && existing.trello_id == id.trello_id)
+}
+
+fn find_by_trello_id<'a, T: StoredItem>(items: &'a [T], id: &ID) -> Option<&'a T> {
+    items.iter().find(|item| item.id().trello_id.is_some() && item.id().trello_id == id.trello_id)
+}
+
+/**
+ * The `items` read from Trello whose parent, as given by `parent_id_of`, is `parent`.
+ */
+fn items_under<T: Clone>(items: &[T], parent: &ID, parent_id_of: fn(&T) -> &ID) -> Vec<T> {
+    items
+        .iter()
+        .filter(|item| parent_id_of(item).trello_id == parent.trello_id)
+        .cloned()
+        .collect()
+}
+
+/**
+ * `item` with `field` set to `value`.
+ */
+fn with_field<T: Serialize + DeserializeOwned>(item: &T, field: &str, value: Value) -> Result<T, Box<dyn std::error::Error>> {
+    let mut item_value = serde_json::to_value(item)?;
+    item_value[field] = value;
+    Ok(serde_json::from_value(item_value)?)
+}
+
+/**
+ * `item` with the Trello ids assigned by a push filled in, for selections made before the push.
+ */
+fn with_trello_ids<T: Serialize + DeserializeOwned>(item: Option<T>, assigned_ids: &[(String, String)]) -> Option<T> {
+    let mut value = serde_json::to_value(item?).ok()?;
+    for (local_id, trello_id) in assigned_ids {
+        fill_trello_id(&mut value, local_id, trello_id);
+    }
+    serde_json::from_value(value).ok()
+}
+
+/**
+ * A place boards and everything on them can be read from and written to.
+ * The `sync_*` methods store what was read from another store and return the items with this
+ * store's ids filled in. Items under the same parent that were synced before but are missing now are
+ * tombstoned, and reads leave them out. Stores that are only ever used as a remote do not need to implement them.
+ */
 #[async_trait]
-pub trait DataStore {
-    async fn get_all_boards() -> Result<Vec<Board>, Box<dyn std::error::Error>>;
-    async fn create_board(name: &str, trello_id: Option<String>) -> Result<Board, Box<dyn std::error::Error>>;
+pub trait DataStore: Send + Sync {
+    async fn get_all_boards(&self) -> Result<Vec<Board>, Box<dyn std::error::Error>>;
+    async fn create_board(&self, name: &str, trello_id: Option<String>) -> Result<Board, Box<dyn std::error::Error>>;
 
     async fn get_all_board_labels(
+        &self,
         board_id: ID,
     ) -> Result<Vec<CardLabel>, Box<dyn std::error::Error>>;
-    async fn delete_board_label(label_id: ID) -> Result<(), Box<dyn std::error::Error>>;
-    async fn update_board_label(label_id: ID, name: &str, color: &str) -> Result<CardLabel, Box<dyn std::error::Error>>;
-    async fn create_board_label(board_id: ID, name: &str, color: &str, trello_id: Option<String>) -> Result<CardLabel, Box<dyn std::error::Error>>;
-
-    async fn get_all_board_lists(board_id: ID) -> Result<Vec<BoardList>, Box<dyn std::error::Error>>;
-    async fn create_board_list(board_id: ID, name: &str, trello_id: Option<String>) -> Result<BoardList, Box<dyn std::error::Error>>;
-
-    async fn get_all_list_cards(list_id: ID) -> Result<Vec<Card>, Box<dyn std::error::Error>>;
-    async fn create_list_card(list_id: ID, name: &str, trello_id: Option<String>) -> Result<Card, Box<dyn std::error::Error>>;
-    async fn update_card(card: &Card) -> Result<Card, Box<dyn std::error::Error>>;
-    async fn get_card_comments(card_id: ID) -> Result<Vec<CardComment>, Box<dyn std::error::Error>>;
-    async fn add_card_comment(card_id: ID, text: &str, trello_id: Option<String>) -> Result<CardComment, Box<dyn std::error::Error>>;
-
-    async fn get_card_checklists(card_id: ID) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>>;
-    async fn create_card_checklist(card_id: ID, name: &str, trello_id: Option<String>) -> Result<CardChecklist, Box<dyn std::error::Error>>;
-    async fn get_checklist_tasks(checklist_id: ID) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>>;
-    async fn create_checklist_task(checklist_id: ID, name: &str, trello_id: Option<String>) -> Result<CardChecklistTask, Box<dyn std::error::Error>>;
-    async fn update_checklist_task(card_id: ID, task: &CardChecklistTask) -> Result<CardChecklistTask, Box<dyn std::error::Error>>;
+    async fn delete_board_label(&self, label_id: ID) -> Result<(), Box<dyn std::error::Error>>;
+    async fn update_board_label(&self, label_id: ID, name: &str, color: &str) -> Result<CardLabel, Box<dyn std::error::Error>>;
+    async fn create_board_label(&self, board_id: ID, name: &str, color: &str, trello_id: Option<String>) -> Result<CardLabel, Box<dyn std::error::Error>>;
+
+    async fn get_all_board_lists(&self, board_id: ID) -> Result<Vec<BoardList>, Box<dyn std::error::Error>>;
+    async fn create_board_list(&self, board_id: ID, name: &str, trello_id: Option<String>) -> Result<BoardList, Box<dyn std::error::Error>>;
+
+    async fn get_all_list_cards(&self, list_id: ID) -> Result<Vec<Card>, Box<dyn std::error::Error>>;
+    async fn get_card(&self, _card_id: ID) -> Result<Card, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn create_list_card(&self, list_id: ID, name: &str, trello_id: Option<String>) -> Result<Card, Box<dyn std::error::Error>>;
+    async fn update_card(&self, card: &Card) -> Result<Card, Box<dyn std::error::Error>>;
+    async fn get_card_comments(&self, card_id: ID) -> Result<Vec<CardComment>, Box<dyn std::error::Error>>;
+    async fn add_card_comment(&self, card_id: ID, text: &str, trello_id: Option<String>) -> Result<CardComment, Box<dyn std::error::Error>>;
+
+    async fn get_card_checklists(&self, card_id: ID) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>>;
+    async fn create_card_checklist(&self, card_id: ID, name: &str, trello_id: Option<String>) -> Result<CardChecklist, Box<dyn std::error::Error>>;
+    async fn get_checklist_tasks(&self, checklist_id: ID) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>>;
+    async fn create_checklist_task(&self, checklist_id: ID, name: &str, trello_id: Option<String>) -> Result<CardChecklistTask, Box<dyn std::error::Error>>;
+    async fn update_checklist_task(&self, card_id: ID, task: &CardChecklistTask) -> Result<CardChecklistTask, Box<dyn std::error::Error>>;
+
+    /**
+     * Everything on `board`. Reads it one list, card and checklist at a time; stores that can get it in fewer requests should.
+     */
+    async fn get_board_snapshot(&self, board: &Board) -> Result<BoardSnapshot, Box<dyn std::error::Error>> {
+        let labels = self.get_all_board_labels(board._id.clone()).await?;
+        let lists = self.get_all_board_lists(board._id.clone()).await?;
+        let mut snapshot = BoardSnapshot {
+            board: board.clone(),
+            labels,
+            lists,
+            cards: vec![],
+            checklists: vec![],
+            tasks: vec![],
+            comments: vec![],
+        };
+        for list in &snapshot.lists {
+            snapshot.cards.extend(self.get_all_list_cards(list._id.clone()).await?);
+        }
+        for card in &snapshot.cards {
+            snapshot.checklists.extend(self.get_card_checklists(card._id.clone()).await?);
+            snapshot.comments.extend(self.get_card_comments(card._id.clone()).await?);
+        }
+        for checklist in &snapshot.checklists {
+            snapshot.tasks.extend(self.get_checklist_tasks(checklist._id.clone()).await?);
+        }
+        Ok(snapshot)
+    }
+
+    /**
+     * The actions on a board since `since`, which is either the id of an earlier action or an RFC 3339 date, newest first.
+     * `None` when there are more of them than can be listed at once, and the whole board has to be read again instead.
+     */
+    async fn get_board_actions(&self, _board_id: ID, _since: &str) -> Result<Option<Vec<BoardAction>>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+
+    async fn sync_boards(&self, _boards: Vec<Board>) -> Result<Vec<Board>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn sync_labels(&self, _board_id: ID, _labels: Vec<CardLabel>) -> Result<Vec<CardLabel>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn sync_lists(&self, _board_id: ID, _lists: Vec<BoardList>) -> Result<Vec<BoardList>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn sync_cards(&self, _list_id: ID, _cards: Vec<Card>) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn sync_checklists(&self, _card_id: ID, _checklists: Vec<CardChecklist>) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn sync_tasks(&self, _checklist_id: ID, _tasks: Vec<CardChecklistTask>) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn sync_comments(&self, _card_id: ID, _comments: Vec<CardComment>) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+
+    /**
+     * Appends a change to the outbox. Entries are returned by `get_outbox` in the order of their `sequence`.
+     */
+    async fn add_to_outbox(&self, _entry: &OutboxEntry) -> Result<(), Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn get_outbox(&self) -> Result<Vec<OutboxEntry>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn remove_from_outbox(&self, _entry_id: &ID) -> Result<(), Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    /**
+     * Records the Trello id of an item that was created offline, on the item itself, on everything
+     * referring to it and on the outbox entries still waiting to be pushed.
+     */
+    async fn set_trello_id(&self, _local_id: &str, _trello_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+
+    /**
+     * Records when the items under `key` (e.g. the lists of one board) were last synced from Trello.
+     */
+    async fn set_last_synced(&self, _key: &str, _instant_seconds: i64) -> Result<(), Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn get_last_synced(&self, _key: &str) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+
+    /**
+     * Records where in Trello's actions feed the items under `key` (e.g. everything on one board) were last synced up to.
+     */
+    async fn set_sync_cursor(&self, _key: &str, _cursor: &str) -> Result<(), Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn get_sync_cursor(&self, _key: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+
+    /**
+     * Adds a conflict, or replaces the stored conflict with the same id. Conflicts are returned by
+     * `get_conflicts` in the order they were first added.
+     */
+    async fn save_conflict(&self, _conflict: &SyncConflict) -> Result<(), Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn get_conflicts(&self) -> Result<Vec<SyncConflict>, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+    async fn remove_conflict(&self, _conflict_id: &ID) -> Result<(), Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+
+    /**
+     * Brings stored data written by older versions up to the current schema, in place. On a dry run nothing
+     * is changed and the report tells what would be.
+     */
+    async fn migrate(&self, _dry_run: bool) -> Result<MigrationReport, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+
+    /**
+     * Deletes the tombstoned items and everything that belonged to them, and returns how many items were deleted.
+     */
+    async fn purge_tombstones(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        Err(Box::new(NotImplError {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::mock_trello::MockTrello;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn stores_are_chosen_from_config() {
+        // The MongoDB store connects when it is created, so only stores that need no server are tried here
+        let path = std::env::temp_dir().join(format!("trello-cli-stores-{}.sqlite3", std::process::id()));
+        let config = Some(json!({
+            "stores": { "remote": "memory", "local": "sqlite" },
+            "sqlite": { "path": path }
+        }));
+        assert!(DataRepository::new(config).await.is_ok());
+        std::fs::remove_file(path).unwrap();
+
+        let config = Some(json!({ "stores": { "remote": "carrier-pigeon" } }));
+        match DataRepository::new(config).await {
+            Ok(_) => panic!("Unknown stores should be rejected"),
+            Err(why) => assert!(why.to_string().contains("carrier-pigeon")),
+        }
+    }
+
+    fn memory_repository() -> DataRepository {
+        DataRepository::with_stores(Box::new(MemoryDataStore::remote()), Box::new(MemoryDataStore::local()))
+    }
+
+    #[tokio::test]
+    async fn created_items_can_be_selected() {
+        let mut repository = memory_repository();
+        let board = repository.create_board("Eng").await.unwrap();
+        assert!(board._id.local_id.is_some());
+        assert!(board._id.trello_id.is_some());
+
+        assert!(repository.select_board("eng").await.unwrap().is_some());
+        repository.create_board_list(None, "Todo").await.unwrap();
+        repository.create_board_list(None, "Done").await.unwrap();
+        let list = repository.select_board_list("todo", None).await.unwrap().unwrap();
+        let mut card = repository.create_list_card(None, "Fix login").await.unwrap();
+
+        card.description = String::from("Steps to reproduce");
+        repository.update_card(&card).await.unwrap();
+        let cards = repository.get_all_list_cards(Some(list)).await.unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].description, "Steps to reproduce");
+        assert_eq!(cards[0]._id.local_id, card._id.local_id);
+
+        let names = repository.get_cached_names();
+        assert_eq!(names.cards, Some(vec![String::from("Fix login")]));
+        assert!(repository.select_board("Ops").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn labels_are_updated_and_deleted_in_both_stores() {
+        let mut repository = memory_repository();
+        let board = repository.create_board("Eng").await.unwrap();
+        repository.create_board_label(Some(board.clone()), "Bug", "red").await.unwrap();
+        repository.update_board_label(None, "Defect", "red").await.unwrap();
+
+        let labels = repository.get_all_board_labels(Some(board.clone())).await.unwrap();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].name, "Defect");
+
+        repository.delete_board_label(None, "defect").await.unwrap();
+        assert!(repository.get_all_board_labels(Some(board)).await.unwrap().is_empty());
+        assert!(repository.delete_board_label(None, "Defect").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn labels_trello_refuses_to_delete_are_kept() {
+        let trello = MockTrello::start().await;
+        let local = MemoryDataStore::local();
+        let remote = TrelloDataStore::new(&trello.config()).unwrap();
+        let mut repository = DataRepository::with_stores(Box::new(remote), Box::new(local.clone()));
+        let board = repository.create_board("Eng").await.unwrap();
+        repository.create_board_label(Some(board.clone()), "Bug", "red").await.unwrap();
+        repository.get_all_board_labels(Some(board.clone())).await.unwrap();
+
+        trello.fail_next(&[403]);
+        assert!(repository.delete_board_label(None, "Bug").await.is_err());
+        assert_eq!(local.get_all_board_labels(board._id).await.unwrap().len(), 1);
+        assert!(local.get_outbox().await.unwrap().is_empty());
+        trello.stop().await;
+    }
+
+    #[tokio::test]
+    async fn reads_trello_refuses_are_not_served_locally() {
+        let trello = MockTrello::start().await;
+        let remote = TrelloDataStore::new(&trello.config()).unwrap();
+        let mut repository = DataRepository::with_stores(Box::new(remote), Box::new(MemoryDataStore::local()));
+        repository.create_board("Eng").await.unwrap();
+        repository.get_all_boards().await.unwrap();
+
+        trello.fail_next(&[401]);
+        assert!(repository.get_all_boards().await.is_err());
+        trello.stop().await;
+    }
+
+    #[tokio::test]
+    async fn offline_writes_are_pushed_in_order() {
+        let remote = MemoryDataStore::remote();
+        let local = MemoryDataStore::local();
+        let mut repository = DataRepository::with_stores(Box::new(remote.clone()), Box::new(local.clone()));
+
+        remote.set_reachable(false);
+        let board = repository.create_board("Eng").await.unwrap();
+        assert!(board._id.trello_id.is_none());
+        repository.select_board("Eng").await.unwrap().unwrap();
+        repository.create_board_list(None, "Todo").await.unwrap();
+        repository.select_board_list("Todo", None).await.unwrap().unwrap();
+        let mut card = repository.create_list_card(None, "Fix login").await.unwrap();
+        card.description = String::from("Steps to reproduce");
+        repository.update_card(&card).await.unwrap();
+        assert_eq!(local.get_outbox().await.unwrap().len(), 4);
+
+        // Trello is back, but the card has to wait for the changes queued before it
+        remote.set_reachable(true);
+        repository.create_list_card(None, "Write docs").await.unwrap();
+        assert!(remote.get_all_boards().await.unwrap().is_empty());
+
+        assert_eq!(repository.push_outbox().await.unwrap(), 5);
+        assert!(local.get_outbox().await.unwrap().is_empty());
+
+        let trello_boards = remote.get_all_boards().await.unwrap();
+        assert_eq!(trello_boards.len(), 1);
+        let trello_lists = remote.get_all_board_lists(trello_boards[0]._id.clone()).await.unwrap();
+        let trello_cards = remote.get_all_list_cards(trello_lists[0]._id.clone()).await.unwrap();
+        let card_names: Vec<&str> = trello_cards.iter().map(|card| card.name.as_str()).collect();
+        assert_eq!(card_names, vec!["Fix login", "Write docs"]);
+        assert_eq!(trello_cards[0].description, "Steps to reproduce");
+
+        let boards = repository.get_all_boards().await.unwrap();
+        assert_eq!(boards.len(), 1);
+        assert_eq!(boards[0]._id.local_id, board._id.local_id);
+        assert_eq!(boards[0]._id.trello_id, trello_boards[0]._id.trello_id);
+    }
+
+    #[tokio::test]
+    async fn reads_fall_back_to_the_local_copy() {
+        let remote = MemoryDataStore::remote();
+        let mut repository = DataRepository::with_stores(Box::new(remote.clone()), Box::new(MemoryDataStore::local()));
+        let board = repository.create_board("Eng").await.unwrap();
+        repository.create_board_list(Some(board.clone()), "Todo").await.unwrap();
+        repository.get_all_board_lists(Some(board.clone())).await.unwrap();
+        assert_eq!(repository.local_read_last_synced(), None);
+
+        remote.set_reachable(false);
+        let lists = repository.get_all_board_lists(Some(board.clone())).await.unwrap();
+        assert_eq!(lists.len(), 1);
+        assert!(repository.local_read_last_synced().unwrap() > 0);
+
+        // Labels were never read from Trello, so there is no sync time
+        assert!(repository.get_all_board_labels(Some(board)).await.unwrap().is_empty());
+        assert_eq!(repository.local_read_last_synced(), Some(0));
+
+        // Served from the cache this time, and still just as stale
+        assert!(repository.get_all_board_labels(None).await.unwrap().is_empty());
+        assert_eq!(repository.local_read_last_synced(), Some(0));
+
+        remote.set_reachable(true);
+        repository.get_all_boards().await.unwrap();
+        assert_eq!(repository.local_read_last_synced(), None);
+    }
+
+    #[tokio::test]
+    async fn items_deleted_in_trello_are_not_read_back() {
+        let remote = MemoryDataStore::remote();
+        let mut repository = DataRepository::with_stores(Box::new(remote.clone()), Box::new(MemoryDataStore::local()));
+        let board = repository.create_board("Eng").await.unwrap();
+        let label = repository.create_board_label(Some(board.clone()), "Bug", "red").await.unwrap();
+        repository.get_all_board_labels(Some(board.clone())).await.unwrap();
+
+        remote.delete_board_label(label._id).await.unwrap();
+        assert!(repository.get_all_board_labels(Some(board.clone())).await.unwrap().is_empty());
+
+        remote.set_reachable(false);
+        assert!(repository.get_all_board_labels(Some(board)).await.unwrap().is_empty());
+        assert_eq!(repository.purge_tombstones().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn offline_card_edits_are_merged_with_trello() {
+        let remote = MemoryDataStore::remote();
+        let mut repository = DataRepository::with_stores(Box::new(remote.clone()), Box::new(MemoryDataStore::local()));
+        let board = repository.create_board("Eng").await.unwrap();
+        let list = repository.create_board_list(Some(board), "Todo").await.unwrap();
+        repository.create_list_card(Some(list.clone()), "Fix login").await.unwrap();
+        let mut card = repository.get_all_list_cards(Some(list.clone())).await.unwrap().remove(0);
+
+        remote.set_reachable(false);
+        card.description = String::from("Steps to reproduce");
+        repository.update_card(&card).await.unwrap();
+
+        remote.set_reachable(true);
+        let mut trello_card = remote.get_card(card._id.clone()).await.unwrap();
+        trello_card.description = String::from("Cannot reproduce");
+        trello_card.due_complete = true;
+        remote.update_card(&trello_card).await.unwrap();
+
+        // The pending edit stays visible until it is pushed
+        let cards = repository.get_all_list_cards(Some(list)).await.unwrap();
+        assert_eq!(cards[0].description, "Steps to reproduce");
+        assert_eq!(cards[0].local_revision, 1);
+
+        repository.push_outbox().await.unwrap();
+        assert_eq!(repository.take_recorded_conflicts(), 1);
+        assert_eq!(repository.local.get_card(card._id.clone()).await.unwrap().local_revision, 0);
+        let conflicts = repository.get_conflicts().await.unwrap();
+        assert_eq!(conflicts[0].fields[0].field, "description");
+        let trello_card = remote.get_card(card._id.clone()).await.unwrap();
+        assert_eq!(trello_card.description, "Cannot reproduce");
+        assert!(trello_card.due_complete);
+
+        repository.resolve_conflict(0, true, &[]).await.unwrap();
+        assert!(repository.get_conflicts().await.unwrap().is_empty());
+        let trello_card = remote.get_card(card._id).await.unwrap();
+        assert_eq!(trello_card.description, "Steps to reproduce");
+        assert!(trello_card.due_complete);
+    }
+
+    #[tokio::test]
+    async fn later_edits_stay_local_until_pushed() {
+        let remote = MemoryDataStore::remote();
+        let mut repository = DataRepository::with_stores(Box::new(remote.clone()), Box::new(MemoryDataStore::local()));
+        let board = repository.create_board("Eng").await.unwrap();
+        let list = repository.create_board_list(Some(board), "Todo").await.unwrap();
+        let mut card = repository.create_list_card(Some(list), "Fix login").await.unwrap();
+
+        remote.set_reachable(false);
+        card.description = String::from("Steps to reproduce");
+        card = repository.update_card(&card).await.unwrap();
+        card.due_complete = true;
+        card = repository.update_card(&card).await.unwrap();
+        assert_eq!(card.local_revision, 2);
+
+        remote.set_reachable(true);
+        repository.push_outbox().await.unwrap();
+        assert_eq!(repository.take_recorded_conflicts(), 0);
+        let local_card = repository.local.get_card(card._id.clone()).await.unwrap();
+        assert_eq!(local_card.local_revision, 0);
+        assert!(local_card.due_complete);
+        let trello_card = remote.get_card(card._id).await.unwrap();
+        assert_eq!(trello_card.description, "Steps to reproduce");
+        assert!(trello_card.due_complete);
+    }
+
+    #[tokio::test]
+    async fn synced_boards_can_be_read_offline() {
+        let remote = MemoryDataStore::remote();
+        let local = MemoryDataStore::local();
+        let board = remote.create_board("Eng", None).await.unwrap();
+        remote.create_board_label(board._id.clone(), "Bug", "red", None).await.unwrap();
+        let list = remote.create_board_list(board._id.clone(), "Todo", None).await.unwrap();
+        let card = remote.create_list_card(list._id.clone(), "Fix login", None).await.unwrap();
+        remote.add_card_comment(card._id.clone(), "Seen on staging", None).await.unwrap();
+        let checklist = remote.create_card_checklist(card._id.clone(), "Steps", None).await.unwrap();
+        remote.create_checklist_task(checklist._id.clone(), "Reproduce", None).await.unwrap();
+
+        let mut repository = DataRepository::with_stores(Box::new(remote.clone()), Box::new(local.clone()));
+        let snapshot = repository.sync_board("eng", false).await.unwrap();
+        assert_eq!(snapshot.describe(), "1 list(s), 1 card(s), 1 label(s), 1 checklist(s), 1 task(s) and 1 comment(s)");
+        assert!(snapshot.tasks[0]._id.local_id.is_some());
+        assert!(repository.sync_board("Ops", false).await.is_err());
+
+        remote.set_reachable(false);
+        let card = local.get_all_list_cards(snapshot.lists[0]._id.clone()).await.unwrap().remove(0);
+        assert_eq!(local.get_card_comments(card._id.clone()).await.unwrap()[0].text, "Seen on staging");
+        let checklists = repository.get_card_checklists(Some(card)).await.unwrap();
+        let tasks = repository.get_checklist_tasks(Some(checklists[0].clone())).await.unwrap();
+        assert_eq!(tasks[0].name, "Reproduce");
+        assert!(repository.local_read_last_synced().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn later_syncs_only_read_what_changed() {
+        let remote = MemoryDataStore::remote();
+        let local = MemoryDataStore::local();
+        let board = remote.create_board("Eng", None).await.unwrap();
+        let todo = remote.create_board_list(board._id.clone(), "Todo", None).await.unwrap();
+        let done = remote.create_board_list(board._id.clone(), "Done", None).await.unwrap();
+        let mut card = remote.create_list_card(todo._id.clone(), "Fix login", None).await.unwrap();
+
+        let mut repository = DataRepository::with_stores(Box::new(remote.clone()), Box::new(local.clone()));
+        assert_eq!(repository.sync_board("Eng", false).await.unwrap().cards.len(), 1);
+        // Catches up on the actions made around the first sync, from then on the cursor is an action id
+        repository.sync_board("Eng", false).await.unwrap();
+
+        remote.add_card_comment(card._id.clone(), "Seen on staging", None).await.unwrap();
+        let snapshot = repository.sync_board("Eng", false).await.unwrap();
+        assert!(snapshot.cards.is_empty());
+        assert_eq!(snapshot.comments.len(), 1);
+
+        card.list_id = done._id.clone();
+        remote.update_card(&card).await.unwrap();
+        let snapshot = repository.sync_board("Eng", false).await.unwrap();
+        assert_eq!(snapshot.cards.len(), 1);
+        assert!(snapshot.comments.is_empty());
+        let synced_done = snapshot.lists.iter().find(|list| list.name == "Done").unwrap();
+        assert_eq!(local.get_all_list_cards(synced_done._id.clone()).await.unwrap().len(), 1);
+        let synced_todo = snapshot.lists.iter().find(|list| list.name == "Todo").unwrap();
+        assert!(local.get_all_list_cards(synced_todo._id.clone()).await.unwrap().is_empty());
+
+        assert!(repository.sync_board("Eng", false).await.unwrap().cards.is_empty());
+        assert_eq!(repository.sync_board("Eng", true).await.unwrap().comments.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn trello_boards_sync_and_read_offline() {
+        let trello = MockTrello::start().await;
+        let local = MemoryDataStore::local();
+        let remote = TrelloDataStore::new(&trello.config()).unwrap();
+        let mut repository = DataRepository::with_stores(Box::new(remote), Box::new(local.clone()));
+
+        let board = repository.create_board("Eng").await.unwrap();
+        let list = repository.create_board_list(Some(board.clone()), "Todo").await.unwrap();
+        let card = repository.create_list_card(Some(list.clone()), "Fix login").await.unwrap();
+        repository.add_card_comment(Some(card.clone()), "Seen on staging").await.unwrap();
+        let checklist = repository.create_card_checklist(Some(card.clone()), "Steps").await.unwrap();
+        repository.create_checklist_task(Some(checklist), "Reproduce").await.unwrap();
+        let snapshot = repository.sync_board("Eng", false).await.unwrap();
+        assert_eq!(snapshot.describe(), "1 list(s), 1 card(s), 0 label(s), 1 checklist(s), 1 task(s) and 1 comment(s)");
+
+        trello.edit_card(card._id.trello_id.as_ref().unwrap(), json!({ "desc": "Steps to reproduce" }));
+        let snapshot = repository.sync_board("Eng", false).await.unwrap();
+        assert_eq!(snapshot.cards[0].description, "Steps to reproduce");
+
+        trello.stop().await;
+        let cards = repository.get_all_list_cards(Some(list)).await.unwrap();
+        assert_eq!(cards[0].name, "Fix login");
+        assert_eq!(cards[0].description, "Steps to reproduce");
+    }
+
+    #[tokio::test]
+    async fn failed_push_keeps_the_change() {
+        let remote = MemoryDataStore::remote();
+        let local = MemoryDataStore::local();
+        let mut repository = DataRepository::with_stores(Box::new(remote.clone()), Box::new(local.clone()));
+
+        remote.set_reachable(false);
+        repository.create_board("Eng").await.unwrap();
+        let why = repository.push_outbox().await.unwrap_err();
+        assert!(why.to_string().contains("create board Eng"));
+        assert_eq!(local.get_outbox().await.unwrap().len(), 1);
+    }
 }
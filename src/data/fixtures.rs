@@ -0,0 +1,150 @@
+use crate::data::InvalidInputError;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/** Stands in for the key and token in recorded requests and responses */
+const REDACTED: &str = "REDACTED";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FixtureMode {
+    Record,
+    Replay,
+}
+
+/**
+ * One request sent to Trello and the response it got. The path is relative to the API's base URL
+ * and includes the query, with the key and token redacted.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+struct Interaction {
+    method: String,
+    path: String,
+    body: Option<Value>,
+    status: u16,
+    response: String,
+}
+
+/**
+ * Request/response pairs kept in a fixture file. In record mode every request the Trello store sends is added
+ * to the file along with its response. In replay mode nothing is sent and each request is answered with the first
+ * recorded response for the same method, path and body that was not served yet, so a recording plays back
+ * in the order it was made.
+ */
+pub struct Fixtures {
+    mode: FixtureMode,
+    path: PathBuf,
+    secrets: Vec<String>,
+    interactions: Mutex<Vec<Interaction>>,
+    replayed: Mutex<Vec<bool>>,
+}
+
+impl Fixtures {
+    /**
+     * Reads the `fixtures` section of the `trello` config, if there is one. `secrets` are left out of everything recorded.
+     */
+    pub fn from_config(config: &Option<Value>, secrets: Vec<String>) -> Result<Option<Fixtures>, Box<dyn std::error::Error>> {
+        let fixtures_config = match config.as_ref().and_then(|config| config.get("trello")).and_then(|trello| trello.get("fixtures")) {
+            Some(fixtures_config) => fixtures_config,
+            None => return Ok(None),
+        };
+
+        let path = match fixtures_config.get("path").and_then(|path| path.as_str()) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                return Err(Box::new(InvalidInputError {
+                    message: Some(String::from("The trello fixtures config needs a path")),
+                }))
+            }
+        };
+        let mode = match fixtures_config.get("mode").and_then(|mode| mode.as_str()).unwrap_or("replay") {
+            "record" => FixtureMode::Record,
+            "replay" => FixtureMode::Replay,
+            other => {
+                return Err(Box::new(InvalidInputError {
+                    message: Some(format!("Unknown fixtures mode {}, expected record or replay", other)),
+                }))
+            }
+        };
+
+        let interactions: Vec<Interaction> = match mode {
+            FixtureMode::Record => vec![],
+            FixtureMode::Replay => serde_json::from_str(&fs::read_to_string(&path)?)?,
+        };
+        Ok(Some(Fixtures {
+            mode,
+            path,
+            secrets: secrets.into_iter().filter(|secret| !secret.is_empty()).collect(),
+            replayed: Mutex::new(vec![false; interactions.len()]),
+            interactions: Mutex::new(interactions),
+        }))
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+        for secret in &self.secrets {
+            redacted = redacted.replace(secret.as_str(), REDACTED);
+        }
+        redacted
+    }
+
+    fn redact_body(&self, body: Option<&Value>) -> Result<Option<Value>, Box<dyn std::error::Error>> {
+        match body {
+            Some(body) => Ok(Some(serde_json::from_str(&self.redact(&body.to_string()))?)),
+            None => Ok(None),
+        }
+    }
+
+    /**
+     * The recorded status and response body for a request.
+     */
+    pub fn replay(&self, method: &str, path: &str, body: Option<&Value>) -> Result<(u16, String), Box<dyn std::error::Error>> {
+        let path = self.redact(path);
+        let body = self.redact_body(body)?;
+        let interactions = self.interactions.lock().unwrap();
+        let mut replayed = self.replayed.lock().unwrap();
+        let index = interactions
+            .iter()
+            .enumerate()
+            .position(|(index, interaction)| !replayed[index] && interaction.method == method && interaction.path == path && interaction.body == body);
+
+        match index {
+            Some(index) => {
+                replayed[index] = true;
+                Ok((interactions[index].status, interactions[index].response.clone()))
+            }
+            None => Err(Box::new(InvalidInputError {
+                message: Some(format!("No recorded response left for {} {}", method, path)),
+            })),
+        }
+    }
+
+    /**
+     * Adds a request and its response to the fixture file.
+     */
+    pub fn record(&self, method: &str, path: &str, body: Option<&Value>, status: u16, response: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let body = self.redact_body(body)?;
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(Interaction {
+            method: method.to_string(),
+            path: self.redact(path),
+            body,
+            status,
+            response: self.redact(response),
+        });
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&*interactions)?)?;
+        Ok(())
+    }
+}
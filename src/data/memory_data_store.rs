@@ -0,0 +1,578 @@
+use crate::data::data_repository::DataStore;
+use crate::data::*;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/**
+ * Store that only lives as long as the process. Nothing is read from or written to disk or the network.
+ * As a remote it stands in for Trello and hands out Trello ids, as a local store it hands out local ids
+ * and keeps the Trello ids it is given, like the MongoDB and SQLite stores.
+ * Clones share the same items, so a test can keep a handle on a store it gave to a repository.
+ */
+#[derive(Clone)]
+pub struct MemoryDataStore {
+    is_remote: bool,
+    is_reachable: Arc<AtomicBool>,
+    items: Arc<Mutex<HashMap<&'static str, Vec<Value>>>>,
+    last_synced: Arc<Mutex<HashMap<String, i64>>>,
+    sync_cursors: Arc<Mutex<HashMap<String, String>>>,
+}
+
+/**
+ * Whether both ids refer to the same item. Ids passed around by the repository may carry
+ * the ids of both stores, so either id matching is enough.
+ */
+fn same_item(a: &ID, b: &ID) -> bool {
+    let trello_ids_match = match (&a.trello_id, &b.trello_id) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => false,
+    };
+    let local_ids_match = match (&a.local_id, &b.local_id) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => false,
+    };
+
+    trello_ids_match || local_ids_match
+}
+
+impl MemoryDataStore {
+    pub fn local() -> MemoryDataStore {
+        MemoryDataStore {
+            is_remote: false,
+            is_reachable: Arc::new(AtomicBool::new(true)),
+            items: Arc::new(Mutex::new(HashMap::new())),
+            last_synced: Arc::new(Mutex::new(HashMap::new())),
+            sync_cursors: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn remote() -> MemoryDataStore {
+        MemoryDataStore { is_remote: true, ..MemoryDataStore::local() }
+    }
+
+    /**
+     * While unreachable every operation fails with `RemoteUnavailableError`, like Trello without a network.
+     */
+    #[cfg(test)]
+    pub fn set_reachable(&self, is_reachable: bool) {
+        self.is_reachable.store(is_reachable, Ordering::SeqCst);
+    }
+
+    fn check_reachable(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.is_reachable.load(Ordering::SeqCst) {
+            Ok(())
+        } else {
+            Err(Box::new(RemoteUnavailableError {}))
+        }
+    }
+
+    fn all<T: StoredItem>(items: &HashMap<&'static str, Vec<Value>>) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let mut all_items: Vec<T> = vec![];
+        for item in items.get(T::TABLE).into_iter().flatten() {
+            all_items.push(serde_json::from_value(item.clone())?);
+        }
+        Ok(all_items)
+    }
+
+    /**
+     * Items under `parent_id` (top level items without one), leaving out tombstoned items.
+     */
+    fn find<T: StoredItem>(items: &HashMap<&'static str, Vec<Value>>, parent_id: Option<&ID>) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let mut found_items: Vec<T> = vec![];
+        for value in items.get(T::TABLE).into_iter().flatten() {
+            if value.get(TOMBSTONE_FIELD).is_some() {
+                continue;
+            }
+
+            let item: T = serde_json::from_value(value.clone())?;
+            let is_match = match (item.parent_id(), parent_id) {
+                (Some(item_parent_id), Some(parent_id)) => same_item(item_parent_id, parent_id),
+                (None, None) => true,
+                _ => false,
+            };
+            if is_match {
+                found_items.push(item);
+            }
+        }
+        Ok(found_items)
+    }
+
+    fn find_by_id<T: StoredItem>(items: &HashMap<&'static str, Vec<Value>>, id: &ID) -> Result<Option<T>, Box<dyn std::error::Error>> {
+        let all_items: Vec<T> = MemoryDataStore::all(items)?;
+        Ok(all_items.into_iter().find(|item| same_item(item.id(), id)))
+    }
+
+    /**
+     * Replaces the stored item with the same id as `item`, or adds it if there is none.
+     */
+    fn save<T: StoredItem>(items: &mut HashMap<&'static str, Vec<Value>>, item: &T) -> Result<(), Box<dyn std::error::Error>> {
+        let all_items: Vec<T> = MemoryDataStore::all(items)?;
+        let position = all_items.iter().position(|existing| same_item(existing.id(), item.id()));
+        let stored_items = items.entry(T::TABLE).or_default();
+        match position {
+            Some(position) => stored_items[position] = serde_json::to_value(item)?,
+            None => stored_items.push(serde_json::to_value(item)?),
+        }
+        Ok(())
+    }
+
+    fn assign_id(&self, id: &mut ID) {
+        if self.is_remote {
+            if id.trello_id.is_none() {
+                id.trello_id = new_local_id();
+            }
+        } else if id.local_id.is_none() {
+            id.local_id = new_local_id();
+        }
+    }
+
+    fn create<T: StoredItem>(&self, mut item: T) -> Result<T, Box<dyn std::error::Error>> {
+        self.check_reachable()?;
+        self.assign_id(item.id_mut());
+        MemoryDataStore::save(&mut self.items.lock().unwrap(), &item)?;
+        Ok(item)
+    }
+
+    fn update<T: StoredItem>(&self, item: &T) -> Result<T, Box<dyn std::error::Error>> {
+        self.check_reachable()?;
+        let mut items = self.items.lock().unwrap();
+        let existing: Option<T> = MemoryDataStore::find_by_id(&items, item.id())?;
+        let mut updated: T = item.clone();
+        match existing {
+            Some(existing) => {
+                if updated.id().local_id.is_none() {
+                    updated.id_mut().local_id = existing.id().local_id.clone();
+                }
+                if updated.id().trello_id.is_none() {
+                    updated.id_mut().trello_id = existing.id().trello_id.clone();
+                }
+            }
+            None if self.is_remote => {
+                return Err(Box::new(InvalidInputError { message: Some(format!("Could not find the item to update in {}", T::TABLE)) }));
+            }
+            None => self.assign_id(updated.id_mut()),
+        }
+
+        MemoryDataStore::save(&mut items, &updated)?;
+        Ok(updated)
+    }
+
+    fn delete<T: StoredItem>(&self, id: &ID) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_reachable()?;
+        let mut items = self.items.lock().unwrap();
+        let mut all_items: Vec<T> = MemoryDataStore::all(&items)?;
+        all_items.retain(|item| !same_item(item.id(), id));
+
+        let mut stored_items: Vec<Value> = vec![];
+        for item in all_items {
+            stored_items.push(serde_json::to_value(item)?);
+        }
+        items.insert(T::TABLE, stored_items);
+        Ok(())
+    }
+
+    fn get<T: StoredItem>(&self, parent_id: Option<&ID>) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        self.check_reachable()?;
+        MemoryDataStore::find(&self.items.lock().unwrap(), parent_id)
+    }
+
+    /**
+     * As a remote, adds an action to the feed of the board the touched items are on, like Trello does for every change.
+     */
+    fn record_action(&self, action_type: &str, list_ids: Vec<ID>, card_id: Option<ID>, checklist_id: Option<ID>) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.is_remote {
+            return Ok(());
+        }
+
+        let mut items = self.items.lock().unwrap();
+        let card_id = match (card_id, &checklist_id) {
+            (None, Some(checklist_id)) => MemoryDataStore::find_by_id::<CardChecklist>(&items, checklist_id)?.map(|checklist| checklist.card_id),
+            (card_id, _) => card_id,
+        };
+        let list_id = match (list_ids.first(), &card_id) {
+            (Some(list_id), _) => Some(list_id.clone()),
+            (None, Some(card_id)) => MemoryDataStore::find_by_id::<Card>(&items, card_id)?.map(|card| card.list_id),
+            (None, None) => None,
+        };
+        let board_id = match list_id {
+            Some(list_id) => MemoryDataStore::find_by_id::<BoardList>(&items, &list_id)?.map(|list| list.board_id),
+            None => None,
+        };
+
+        if let Some(board_id) = board_id {
+            let mut action = BoardAction {
+                _id: ID { trello_id: None, local_id: None },
+                action_type: action_type.to_string(),
+                board_id,
+                list_ids,
+                card_id,
+                checklist_id,
+                date_instant_seconds: Utc::now().timestamp(),
+            };
+            self.assign_id(&mut action._id);
+            MemoryDataStore::save(&mut items, &action)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Same upsert semantics as the other local stores: items that already exist (matched by Trello id)
+     * keep their local id and are updated, the rest are added with a new one. Items under `parent_id`
+     * that Trello no longer returned are tombstoned, and the ones that were created offline and not
+     * pushed yet are returned after the synced ones.
+     */
+    fn sync<T: StoredItem>(&self, parent_id: Option<&ID>, remote_items: Vec<T>) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        if self.is_remote {
+            return Err(Box::new(NotImplError {}));
+        }
+
+        let mut items = self.items.lock().unwrap();
+        let (unpushed_items, mut missing_items): (Vec<T>, Vec<T>) = MemoryDataStore::find::<T>(&items, parent_id)?
+            .into_iter()
+            .partition(|item| item.id().trello_id.is_none());
+
+        let mut items_with_ids: Vec<T> = vec![];
+        for mut item in remote_items {
+            let lookup_id = ID { trello_id: item.id().trello_id.clone(), local_id: None };
+            let existing: Option<T> = MemoryDataStore::find_by_id(&items, &lookup_id)?;
+            item.id_mut().local_id = existing.and_then(|existing| existing.id().local_id.clone()).or_else(new_local_id);
+            MemoryDataStore::save(&mut items, &item)?;
+            missing_items.retain(|missing| !same_item(missing.id(), item.id()));
+            items_with_ids.push(item);
+        }
+
+        let tombstoned_instant_seconds = Utc::now().timestamp();
+        for value in items.entry(T::TABLE).or_default().iter_mut() {
+            let id: ID = serde_json::from_value(value["_id"].clone())?;
+            if missing_items.iter().any(|missing| same_item(missing.id(), &id)) {
+                value[TOMBSTONE_FIELD] = Value::from(tombstoned_instant_seconds);
+            }
+        }
+
+        items_with_ids.extend(unpushed_items);
+        Ok(items_with_ids)
+    }
+}
+
+#[async_trait]
+impl DataStore for MemoryDataStore {
+    async fn get_all_boards(&self) -> Result<Vec<Board>, Box<dyn std::error::Error>> {
+        self.get(None)
+    }
+
+    async fn create_board(&self, name: &str, trello_id: Option<String>) -> Result<Board, Box<dyn std::error::Error>> {
+        self.create(Board {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+        })
+    }
+
+    async fn get_all_board_labels(&self, board_id: ID) -> Result<Vec<CardLabel>, Box<dyn std::error::Error>> {
+        self.get(Some(&board_id))
+    }
+
+    async fn delete_board_label(&self, label_id: ID) -> Result<(), Box<dyn std::error::Error>> {
+        self.delete::<CardLabel>(&label_id)
+    }
+
+    async fn update_board_label(&self, label_id: ID, name: &str, color: &str) -> Result<CardLabel, Box<dyn std::error::Error>> {
+        self.check_reachable()?;
+        let existing: Option<CardLabel> = MemoryDataStore::find_by_id(&self.items.lock().unwrap(), &label_id)?;
+        match existing {
+            Some(mut label) => {
+                label.name = name.to_string();
+                label.color = color.to_string();
+                self.update(&label)
+            }
+            None => Err(Box::new(InvalidInputError { message: Some(String::from("Could not find the label to update")) })),
+        }
+    }
+
+    async fn create_board_label(&self, board_id: ID, name: &str, color: &str, trello_id: Option<String>) -> Result<CardLabel, Box<dyn std::error::Error>> {
+        self.create(CardLabel {
+            _id: ID { trello_id, local_id: None },
+            board_id,
+            name: name.to_string(),
+            color: color.to_string(),
+        })
+    }
+
+    async fn get_all_board_lists(&self, board_id: ID) -> Result<Vec<BoardList>, Box<dyn std::error::Error>> {
+        self.get(Some(&board_id))
+    }
+
+    async fn create_board_list(&self, board_id: ID, name: &str, trello_id: Option<String>) -> Result<BoardList, Box<dyn std::error::Error>> {
+        self.create(BoardList {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+            board_id,
+        })
+    }
+
+    async fn get_all_list_cards(&self, list_id: ID) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        self.get(Some(&list_id))
+    }
+
+    async fn get_card(&self, card_id: ID) -> Result<Card, Box<dyn std::error::Error>> {
+        self.check_reachable()?;
+        match MemoryDataStore::find_by_id(&self.items.lock().unwrap(), &card_id)? {
+            Some(card) => Ok(card),
+            None => Err(Box::new(InvalidInputError { message: Some(String::from("Could not find the card")) })),
+        }
+    }
+
+    async fn create_list_card(&self, list_id: ID, name: &str, trello_id: Option<String>) -> Result<Card, Box<dyn std::error::Error>> {
+        let card = self.create(Card {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+            description: String::from(""),
+            due_date_instant_seconds: 0,
+            due_complete: false,
+            label_ids: vec![],
+            checklists_ids: vec![],
+            list_id,
+            last_activity_instant_seconds: 0,
+            local_revision: 0,
+        })?;
+        self.record_action("createCard", vec![card.list_id.clone()], Some(card._id.clone()), None)?;
+        Ok(card)
+    }
+
+    async fn update_card(&self, card: &Card) -> Result<Card, Box<dyn std::error::Error>> {
+        let list_before: Option<ID> = MemoryDataStore::find_by_id::<Card>(&self.items.lock().unwrap(), &card._id)?.map(|existing| existing.list_id);
+        let updated = self.update(card)?;
+
+        let mut list_ids = vec![updated.list_id.clone()];
+        if let Some(list_before) = list_before.filter(|list_before| !same_item(list_before, &updated.list_id)) {
+            list_ids.insert(0, list_before);
+        }
+        self.record_action("updateCard", list_ids, Some(updated._id.clone()), None)?;
+        Ok(updated)
+    }
+
+    async fn get_card_comments(&self, card_id: ID) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
+        self.get(Some(&card_id))
+    }
+
+    async fn add_card_comment(&self, card_id: ID, text: &str, trello_id: Option<String>) -> Result<CardComment, Box<dyn std::error::Error>> {
+        let comment = self.create(CardComment {
+            _id: ID { trello_id, local_id: None },
+            text: text.to_string(),
+            commenter_name: String::from(""),
+            comment_time_instant_seconds: 0,
+            card_id,
+        })?;
+        self.record_action("commentCard", vec![], Some(comment.card_id.clone()), None)?;
+        Ok(comment)
+    }
+
+    async fn get_card_checklists(&self, card_id: ID) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
+        self.get(Some(&card_id))
+    }
+
+    async fn create_card_checklist(&self, card_id: ID, name: &str, trello_id: Option<String>) -> Result<CardChecklist, Box<dyn std::error::Error>> {
+        let checklist = self.create(CardChecklist {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+            card_id,
+        })?;
+        self.record_action("addChecklistToCard", vec![], Some(checklist.card_id.clone()), Some(checklist._id.clone()))?;
+        Ok(checklist)
+    }
+
+    async fn get_checklist_tasks(&self, checklist_id: ID) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        self.get(Some(&checklist_id))
+    }
+
+    async fn create_checklist_task(&self, checklist_id: ID, name: &str, trello_id: Option<String>) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
+        let task = self.create(CardChecklistTask {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+            is_complete: false,
+            checklist_id,
+            local_revision: 0,
+        })?;
+        self.record_action("createCheckItem", vec![], None, Some(task.checklist_id.clone()))?;
+        Ok(task)
+    }
+
+    async fn update_checklist_task(&self, card_id: ID, task: &CardChecklistTask) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
+        let updated = self.update(task)?;
+        self.record_action("updateCheckItemStateOnCard", vec![], Some(card_id), Some(updated.checklist_id.clone()))?;
+        Ok(updated)
+    }
+
+    async fn get_board_actions(&self, board_id: ID, since: &str) -> Result<Option<Vec<BoardAction>>, Box<dyn std::error::Error>> {
+        let actions: Vec<BoardAction> = self.get(Some(&board_id))?;
+        let newer_actions: Vec<BoardAction> = match actions.iter().position(|action| action._id.trello_id.as_deref() == Some(since)) {
+            Some(position) => actions[position + 1..].to_vec(),
+            None => {
+                let since_instant_seconds = since.parse::<DateTime<Utc>>().map(|date| date.timestamp()).unwrap_or(0);
+                actions.into_iter().filter(|action| action.date_instant_seconds >= since_instant_seconds).collect()
+            }
+        };
+        Ok(Some(newer_actions.into_iter().rev().collect()))
+    }
+
+    async fn sync_boards(&self, boards: Vec<Board>) -> Result<Vec<Board>, Box<dyn std::error::Error>> {
+        self.sync(None, boards)
+    }
+
+    async fn sync_labels(&self, board_id: ID, labels: Vec<CardLabel>) -> Result<Vec<CardLabel>, Box<dyn std::error::Error>> {
+        self.sync(Some(&board_id), labels)
+    }
+
+    async fn sync_lists(&self, board_id: ID, lists: Vec<BoardList>) -> Result<Vec<BoardList>, Box<dyn std::error::Error>> {
+        self.sync(Some(&board_id), lists)
+    }
+
+    async fn sync_cards(&self, list_id: ID, cards: Vec<Card>) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        self.sync(Some(&list_id), cards)
+    }
+
+    async fn sync_checklists(&self, card_id: ID, checklists: Vec<CardChecklist>) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
+        self.sync(Some(&card_id), checklists)
+    }
+
+    async fn sync_tasks(&self, checklist_id: ID, tasks: Vec<CardChecklistTask>) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        self.sync(Some(&checklist_id), tasks)
+    }
+
+    async fn sync_comments(&self, card_id: ID, comments: Vec<CardComment>) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
+        self.sync(Some(&card_id), comments)
+    }
+
+    async fn add_to_outbox(&self, entry: &OutboxEntry) -> Result<(), Box<dyn std::error::Error>> {
+        MemoryDataStore::save(&mut self.items.lock().unwrap(), entry)
+    }
+
+    async fn get_outbox(&self) -> Result<Vec<OutboxEntry>, Box<dyn std::error::Error>> {
+        // Stable, so entries queued before they had a sequence keep the order they were added in
+        let mut entries: Vec<OutboxEntry> = self.get(None)?;
+        entries.sort_by_key(|entry| entry.sequence);
+        Ok(entries)
+    }
+
+    async fn remove_from_outbox(&self, entry_id: &ID) -> Result<(), Box<dyn std::error::Error>> {
+        self.delete::<OutboxEntry>(entry_id)
+    }
+
+    async fn set_trello_id(&self, local_id: &str, trello_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut items = self.items.lock().unwrap();
+        for stored_items in items.values_mut() {
+            for item in stored_items.iter_mut() {
+                fill_trello_id(item, local_id, trello_id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_last_synced(&self, key: &str, instant_seconds: i64) -> Result<(), Box<dyn std::error::Error>> {
+        self.last_synced.lock().unwrap().insert(key.to_string(), instant_seconds);
+        Ok(())
+    }
+
+    async fn get_last_synced(&self, key: &str) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        Ok(self.last_synced.lock().unwrap().get(key).copied())
+    }
+
+    async fn set_sync_cursor(&self, key: &str, cursor: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.sync_cursors.lock().unwrap().insert(key.to_string(), cursor.to_string());
+        Ok(())
+    }
+
+    async fn get_sync_cursor(&self, key: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(self.sync_cursors.lock().unwrap().get(key).cloned())
+    }
+
+    async fn save_conflict(&self, conflict: &SyncConflict) -> Result<(), Box<dyn std::error::Error>> {
+        MemoryDataStore::save(&mut self.items.lock().unwrap(), conflict)
+    }
+
+    async fn get_conflicts(&self) -> Result<Vec<SyncConflict>, Box<dyn std::error::Error>> {
+        self.get(None)
+    }
+
+    async fn remove_conflict(&self, conflict_id: &ID) -> Result<(), Box<dyn std::error::Error>> {
+        self.delete::<SyncConflict>(conflict_id)
+    }
+
+    async fn purge_tombstones(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut items = self.items.lock().unwrap();
+        let mut purged: usize = 0;
+        for table in ["boards", "labels", "lists", "cards", "comments", "checklists", "tasks"] {
+            let stored_items = items.entry(table).or_default();
+            let count = stored_items.len();
+            stored_items.retain(|item| item.get(TOMBSTONE_FIELD).is_none());
+            purged += count - stored_items.len();
+        }
+
+        for (table, parent_field, parent_table) in CHILD_TABLES {
+            let mut parent_ids: Vec<ID> = vec![];
+            for parent in items.get(parent_table).into_iter().flatten() {
+                parent_ids.push(serde_json::from_value(parent["_id"].clone())?);
+            }
+
+            let stored_items = items.entry(table).or_default();
+            let count = stored_items.len();
+            stored_items.retain(|item| match serde_json::from_value::<ID>(item[parent_field].clone()) {
+                Ok(parent_id) => parent_ids.iter().any(|id| same_item(id, &parent_id)),
+                Err(_) => true,
+            });
+            purged += count - stored_items.len();
+        }
+
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn remote_hands_out_trello_ids_and_local_keeps_them() {
+        let remote = MemoryDataStore::remote();
+        let local = MemoryDataStore::local();
+
+        let trello_board = remote.create_board("Eng", None).await.unwrap();
+        assert!(trello_board._id.trello_id.is_some());
+        assert!(trello_board._id.local_id.is_none());
+
+        let board = local.create_board("Eng", trello_board._id.trello_id.clone()).await.unwrap();
+        assert!(board._id.local_id.is_some());
+        assert_eq!(board._id.trello_id, trello_board._id.trello_id);
+
+        let synced = local.sync_boards(remote.get_all_boards().await.unwrap()).await.unwrap();
+        assert_eq!(synced.len(), 1);
+        assert_eq!(synced[0]._id.local_id, board._id.local_id);
+        assert!(remote.sync_boards(vec![]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn items_are_found_by_either_parent_id() {
+        let remote = MemoryDataStore::remote();
+        let board = remote.create_board("Eng", None).await.unwrap();
+        let board_with_local_id = ID { trello_id: board._id.trello_id.clone(), local_id: new_local_id() };
+        let list = remote.create_board_list(board_with_local_id, "Todo", None).await.unwrap();
+
+        let mut card = remote.create_list_card(list._id.clone(), "Fix login", None).await.unwrap();
+        card.description = String::from("Steps to reproduce");
+        remote.update_card(&card).await.unwrap();
+
+        assert_eq!(remote.get_all_board_lists(board._id.clone()).await.unwrap().len(), 1);
+        let cards = remote.get_all_list_cards(list._id).await.unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].description, "Steps to reproduce");
+
+        let label = remote.create_board_label(board._id.clone(), "Bug", "red", None).await.unwrap();
+        remote.delete_board_label(label._id).await.unwrap();
+        assert!(remote.get_all_board_labels(board._id).await.unwrap().is_empty());
+    }
+}
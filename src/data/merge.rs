@@ -0,0 +1,128 @@
+use crate::data::FieldConflict;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+/**
+ * Fields that are not edited by users and always come from Trello's version.
+ */
+const REMOTE_ONLY_FIELDS: [&str; 3] = ["_id", "checklists_ids", "last_activity_instant_seconds"];
+
+/**
+ * Counts the edits made here that Trello does not have yet, see `Card::local_revision`.
+ */
+const LOCAL_REVISION_FIELD: &str = "local_revision";
+
+/**
+ * Three-way merge of `local` and `remote`, two versions of an item that both started out as `base`.
+ * A field changed on one side only takes that side's value. A field both sides changed, to different values,
+ * is returned as a conflict and keeps Trello's value in the merged item. The merged item keeps the ids of `local`.
+ */
+pub(crate) fn merge<T: Serialize + DeserializeOwned>(base: &T, local: &T, remote: &T) -> Result<(T, Vec<FieldConflict>), Box<dyn std::error::Error>> {
+    let base = serde_json::to_value(base)?;
+    let remote = serde_json::to_value(remote)?;
+    let mut merged = serde_json::to_value(local)?;
+
+    let mut conflicts: Vec<FieldConflict> = vec![];
+    if let (Some(merged_fields), Some(remote_fields)) = (merged.as_object_mut(), remote.as_object()) {
+        for (field, remote_value) in remote_fields {
+            if field == LOCAL_REVISION_FIELD {
+                // Not an edit of its own, the merged item has every edit either side had
+                let revision = remote_value.as_u64().max(merged_fields.get(field).and_then(|value| value.as_u64()));
+                merged_fields.insert(field.clone(), Value::from(revision.unwrap_or(0)));
+                continue;
+            }
+            if REMOTE_ONLY_FIELDS.contains(&field.as_str()) {
+                if field != "_id" {
+                    merged_fields.insert(field.clone(), remote_value.clone());
+                }
+                continue;
+            }
+
+            let base_value = base.get(field).unwrap_or(&Value::Null);
+            let local_value = merged_fields.get(field).cloned().unwrap_or(Value::Null);
+            if same_value(&local_value, remote_value) || same_value(remote_value, base_value) {
+                continue;
+            }
+
+            if !same_value(&local_value, base_value) {
+                conflicts.push(FieldConflict { field: field.clone(), local: local_value, remote: remote_value.clone() });
+            }
+            merged_fields.insert(field.clone(), remote_value.clone());
+        }
+    }
+
+    Ok((serde_json::from_value(merged)?, conflicts))
+}
+
+/**
+ * Whether two field values are the same once local ids are left out, since only one side may know them.
+ */
+fn same_value(a: &Value, b: &Value) -> bool {
+    without_local_ids(a) == without_local_ids(b)
+}
+
+fn without_local_ids(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .filter(|(key, _)| key.as_str() != "local_id")
+                .map(|(key, value)| (key.clone(), without_local_ids(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(without_local_ids).collect()),
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{CardChecklistTask, ID};
+
+    fn task(name: &str, is_complete: bool) -> CardChecklistTask {
+        CardChecklistTask {
+            _id: ID { trello_id: Some(String::from("t1")), local_id: None },
+            name: name.to_string(),
+            is_complete,
+            checklist_id: ID { trello_id: Some(String::from("c1")), local_id: None },
+            local_revision: 0,
+        }
+    }
+
+    #[test]
+    fn changes_on_either_side_are_kept() {
+        let mut local = task("Write tests", true);
+        local._id.local_id = Some(String::from("local"));
+        let (merged, conflicts) = merge(&task("Write tests", false), &local, &task("Write more tests", false)).unwrap();
+
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.name, "Write more tests");
+        assert!(merged.is_complete);
+        assert_eq!(merged._id.local_id, Some(String::from("local")));
+    }
+
+    #[test]
+    fn fields_changed_on_both_sides_conflict() {
+        let (merged, conflicts) = merge(&task("Write tests", false), &task("Write docs", true), &task("Write more tests", true)).unwrap();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].field, "name");
+        assert_eq!(conflicts[0].local, Value::from("Write docs"));
+        assert_eq!(merged.name, "Write more tests");
+    }
+
+    #[test]
+    fn local_revisions_never_conflict() {
+        let mut base = task("Write tests", false);
+        base.local_revision = 1;
+        let mut local = task("Write tests", true);
+        local.local_revision = 2;
+        let (merged, conflicts) = merge(&base, &local, &task("Write tests", false)).unwrap();
+
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.local_revision, 2);
+    }
+}
@@ -0,0 +1,438 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use std::sync::{Arc, Mutex};
+
+const KEY: &str = "mock-key";
+const TOKEN: &str = "mock-token";
+const MEMBER_NAME: &str = "Mock Member";
+
+/**
+ * Stand-in for Trello's REST API on a local port, for tests that go through `TrelloDataStore`.
+ * It answers the endpoints the store calls, with items shaped like Trello's, and records the actions
+ * Trello would add to a board's actions feed. Requests with another key or token are refused like Trello does.
+ */
+pub struct MockTrello {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+    server: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct MockState {
+    next_id: u64,
+    boards: Vec<Value>,
+    labels: Vec<Value>,
+    lists: Vec<Value>,
+    cards: Vec<Value>,
+    checklists: Vec<Value>,
+    check_items: Vec<Value>,
+    actions: Vec<Value>,
+    failures: Vec<u16>,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl MockTrello {
+    pub async fn start() -> MockTrello {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/1", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let server_state = state.clone();
+        let server = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, server_state.clone()));
+            }
+        });
+
+        MockTrello { url, state, server }
+    }
+
+    /**
+     * Config for a `TrelloDataStore` that talks to this server.
+     */
+    pub fn config(&self) -> Option<Value> {
+        Some(json!({
+            "trello": {
+                "developer_api_key": KEY,
+                "developer_api_token": TOKEN,
+                "api_url": self.url
+            }
+        }))
+    }
+
+    /**
+     * Changes fields of a card as if someone else edited it on Trello.
+     */
+    pub fn edit_card(&self, card_id: &str, fields: Value) {
+        let mut state = self.state.lock().unwrap();
+        state.update_card(card_id, fields.as_object().unwrap()).unwrap();
+    }
+
+    /**
+     * Answers the next requests with these statuses, one each and in order, before handling requests again.
+     * Rate limited requests are told to retry right away.
+     */
+    pub fn fail_next(&self, statuses: &[u16]) {
+        self.state.lock().unwrap().failures.extend_from_slice(statuses);
+    }
+
+    /**
+     * Stops answering, so the next requests fail to connect like they do without a network.
+     */
+    pub async fn stop(self) {
+        self.server.abort();
+        let _ = self.server.await;
+    }
+}
+
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let mut request: Vec<u8> = vec![];
+    let mut buffer = [0_u8; 4096];
+    let head_length = loop {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+        if let Some(position) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&request[..head_length]).to_string();
+    let content_length: usize = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+    while request.len() < head_length + content_length {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    }
+
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default().to_string();
+    let body = &request[head_length..head_length + content_length];
+
+    let authorization = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .map(|(_, value)| value.trim().to_string())
+        .unwrap_or_default();
+
+    let response = state.lock().unwrap().handle(&method, &target, &authorization, body);
+    let reason = match response.status {
+        200 => "OK",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        _ => "Internal Server Error",
+    };
+    let content_type = if response.status == 200 { "application/json" } else { "text/plain" };
+    let message = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        content_type,
+        if response.status == 429 { "Retry-After: 0\r\n" } else { "" },
+        response.body.len(),
+        response.body
+    );
+    let _ = stream.write_all(message.as_bytes()).await;
+}
+
+/**
+ * Decodes a query parameter, where `+` and `%20` both stand for a space.
+ */
+fn decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded: Vec<u8> = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' => match encoded.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    index += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn not_found() -> Response {
+    Response { status: 404, body: String::from("The requested resource was not found.") }
+}
+
+fn ok(body: Value) -> Response {
+    Response { status: 200, body: body.to_string() }
+}
+
+fn with_id<'a>(items: &'a mut [Value], id: &str) -> Option<&'a mut Value> {
+    items.iter_mut().find(|item| item["id"] == id)
+}
+
+fn children(items: &[Value], parent_field: &str, parent_id: &str) -> Value {
+    Value::Array(items.iter().filter(|item| item[parent_field] == parent_id).cloned().collect())
+}
+
+impl MockState {
+    fn handle(&mut self, method: &str, target: &str, authorization: &str, body: &[u8]) -> Response {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let mut fields: Map<String, Value> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (decode(name), Value::String(decode(value))))
+            .collect();
+        let header_credentials = format!("OAuth oauth_consumer_key=\"{}\", oauth_token=\"{}\"", KEY, TOKEN);
+        let query_credentials = fields.get("key") == Some(&Value::from(KEY)) && fields.get("token") == Some(&Value::from(TOKEN));
+        if authorization != header_credentials && !query_credentials {
+            return Response { status: 401, body: String::from("invalid key") };
+        }
+        if !self.failures.is_empty() {
+            let status = self.failures.remove(0);
+            let body = if status == 429 { r#"{"error":"API_TOKEN_LIMIT_EXCEEDED","message":"Rate limit exceeded"}"# } else { "Internal Server Error" };
+            return Response { status, body: String::from(body) };
+        }
+        if let Ok(Value::Object(body_fields)) = serde_json::from_slice::<Value>(body) {
+            fields.extend(body_fields);
+        }
+
+        let segments: Vec<&str> = path.trim_start_matches("/1").split('/').filter(|segment| !segment.is_empty()).collect();
+        let name = fields.get("name").and_then(|name| name.as_str()).unwrap_or_default().to_string();
+        match (method, segments.as_slice()) {
+            ("GET", ["members", "me", "boards"]) => ok(Value::Array(self.boards.clone())),
+            ("POST", ["boards"]) => {
+                let board = json!({ "id": self.new_id(), "name": name });
+                self.boards.push(board.clone());
+                ok(board)
+            }
+            ("GET", ["boards", board_id]) => self.board_snapshot(board_id),
+
+            ("GET", ["boards", board_id, "labels"]) => ok(children(&self.labels, "idBoard", board_id)),
+            ("POST", ["boards", board_id, "labels"]) => {
+                let label = json!({ "id": self.new_id(), "idBoard": board_id, "name": name, "color": fields["color"] });
+                self.labels.push(label.clone());
+                ok(label)
+            }
+            ("PUT", ["labels", label_id]) => match with_id(&mut self.labels, label_id) {
+                Some(label) => {
+                    label["name"] = Value::from(name);
+                    label["color"] = fields["color"].clone();
+                    ok(label.clone())
+                }
+                None => not_found(),
+            },
+            ("DELETE", ["labels", label_id]) => {
+                self.labels.retain(|label| label["id"] != *label_id);
+                ok(json!({ "limits": {} }))
+            }
+
+            ("GET", ["boards", board_id, "lists"]) => ok(children(&self.lists, "idBoard", board_id)),
+            ("POST", ["boards", board_id, "lists"]) => {
+                let list = json!({ "id": self.new_id(), "idBoard": board_id, "name": name, "closed": false });
+                self.lists.push(list.clone());
+                self.record_action("createList", json!({ "board": { "id": board_id }, "list": { "id": list["id"] } }));
+                ok(list)
+            }
+
+            ("GET", ["lists", list_id, "cards"]) => ok(children(&self.cards, "idList", list_id)),
+            ("GET", ["cards", card_id]) => match with_id(&mut self.cards, card_id) {
+                Some(card) => ok(card.clone()),
+                None => not_found(),
+            },
+            ("POST", ["cards"]) => {
+                let list_id = fields.get("idList").and_then(|list_id| list_id.as_str()).unwrap_or_default();
+                let board_id = match with_id(&mut self.lists, list_id) {
+                    Some(list) => list["idBoard"].clone(),
+                    None => return not_found(),
+                };
+                let card = json!({
+                    "id": self.new_id(), "idBoard": board_id, "idList": list_id, "name": name, "desc": "",
+                    "due": null, "dueComplete": false, "idLabels": [], "idChecklists": [], "dateLastActivity": now()
+                });
+                self.cards.push(card.clone());
+                self.record_action("createCard", json!({ "board": { "id": board_id }, "list": { "id": list_id }, "card": { "id": card["id"] } }));
+                ok(card)
+            }
+            ("PUT", ["cards", card_id]) => match self.update_card(card_id, &fields) {
+                Some(card) => ok(card),
+                None => not_found(),
+            },
+
+            ("GET", ["cards", card_id, "actions"]) => {
+                let mut comments: Vec<Value> =
+                    self.actions.iter().filter(|action| action["type"] == "commentCard" && action["data"]["card"]["id"] == *card_id).cloned().collect();
+                comments.reverse();
+                ok(Value::Array(comments))
+            }
+            ("POST", ["cards", card_id, "actions", "comments"]) => {
+                let board_id = match with_id(&mut self.cards, card_id) {
+                    Some(card) => card["idBoard"].clone(),
+                    None => return not_found(),
+                };
+                let data = json!({ "board": { "id": board_id }, "card": { "id": card_id }, "text": fields["text"] });
+                ok(self.record_action("commentCard", data))
+            }
+
+            ("GET", ["cards", card_id, "checklists"]) => ok(self.checklists_with_items("idCard", card_id)),
+            ("POST", ["cards", card_id, "checklists"]) => {
+                let checklist_id = Value::from(self.new_id());
+                let board_id = match with_id(&mut self.cards, card_id) {
+                    Some(card) => {
+                        card["idChecklists"].as_array_mut().unwrap().push(checklist_id.clone());
+                        card["idBoard"].clone()
+                    }
+                    None => return not_found(),
+                };
+                let checklist = json!({ "id": checklist_id, "idBoard": board_id, "idCard": card_id, "name": name, "checkItems": [] });
+                self.checklists.push(checklist.clone());
+                self.record_action("addChecklistToCard", json!({ "board": { "id": board_id }, "card": { "id": card_id }, "checklist": { "id": checklist_id } }));
+                ok(checklist)
+            }
+
+            ("GET", ["checklists", checklist_id, "checkItems"]) => ok(children(&self.check_items, "idChecklist", checklist_id)),
+            ("POST", ["checklists", checklist_id, "checkItems"]) => {
+                let (board_id, card_id) = match with_id(&mut self.checklists, checklist_id) {
+                    Some(checklist) => (checklist["idBoard"].clone(), checklist["idCard"].clone()),
+                    None => return not_found(),
+                };
+                let task = json!({ "id": self.new_id(), "idChecklist": checklist_id, "name": name, "state": "incomplete" });
+                self.check_items.push(task.clone());
+                self.record_action(
+                    "createCheckItem",
+                    json!({ "board": { "id": board_id }, "card": { "id": card_id }, "checklist": { "id": checklist_id }, "checkItem": { "id": task["id"] } }),
+                );
+                ok(task)
+            }
+            ("PUT", ["cards", card_id, "checkItem", task_id]) | ("PUT", ["cards", card_id, "checkitem", task_id]) => {
+                let task = match with_id(&mut self.check_items, task_id) {
+                    Some(task) => {
+                        if !name.is_empty() {
+                            task["name"] = Value::from(name);
+                        }
+                        if let Some(state) = fields.get("state") {
+                            task["state"] = state.clone();
+                        }
+                        task.clone()
+                    }
+                    None => return not_found(),
+                };
+                let checklist_id = task["idChecklist"].as_str().unwrap_or_default().to_string();
+                let board_id = with_id(&mut self.checklists, &checklist_id).map(|checklist| checklist["idBoard"].clone()).unwrap_or(Value::Null);
+                self.record_action(
+                    "updateCheckItemStateOnCard",
+                    json!({ "board": { "id": board_id }, "card": { "id": card_id }, "checklist": { "id": checklist_id }, "checkItem": { "id": task_id } }),
+                );
+                ok(task)
+            }
+
+            ("GET", ["boards", board_id, "actions"]) => {
+                let since = fields.get("since").and_then(|since| since.as_str()).unwrap_or_default().to_string();
+                let limit = fields.get("limit").and_then(|limit| limit.as_str()).and_then(|limit| limit.parse().ok()).unwrap_or(50);
+                let since_date = since.parse::<DateTime<Utc>>().ok();
+                let mut actions: Vec<Value> = self
+                    .actions
+                    .iter()
+                    .filter(|action| action["data"]["board"]["id"] == *board_id)
+                    .filter(|action| match since_date {
+                        Some(since_date) => action["date"].as_str().and_then(|date| date.parse::<DateTime<Utc>>().ok()).is_some_and(|date| date > since_date),
+                        None => action["id"].as_str().unwrap_or_default() > since.as_str(),
+                    })
+                    .cloned()
+                    .collect();
+                actions.reverse();
+                actions.truncate(limit);
+                ok(Value::Array(actions))
+            }
+
+            _ => not_found(),
+        }
+    }
+
+    /**
+     * Ids that sort in the order they were handed out, like Trello's which start with a timestamp.
+     */
+    fn new_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("{:024x}", self.next_id)
+    }
+
+    fn record_action(&mut self, action_type: &str, data: Value) -> Value {
+        let action = json!({
+            "id": self.new_id(),
+            "type": action_type,
+            "date": now(),
+            "memberCreator": { "fullName": MEMBER_NAME },
+            "data": data
+        });
+        self.actions.push(action.clone());
+        action
+    }
+
+    fn update_card(&mut self, card_id: &str, fields: &Map<String, Value>) -> Option<Value> {
+        let card = with_id(&mut self.cards, card_id)?;
+        let list_before = card["idList"].clone();
+        for field in ["name", "desc", "idList", "idLabels", "due", "dueComplete"] {
+            if let Some(value) = fields.get(field) {
+                card[field] = value.clone();
+            }
+        }
+        card["dateLastActivity"] = Value::from(now());
+        let card = card.clone();
+
+        let mut data = json!({ "board": { "id": card["idBoard"] }, "card": { "id": card_id } });
+        if card["idList"] != list_before {
+            data["listBefore"] = json!({ "id": list_before });
+            data["listAfter"] = json!({ "id": card["idList"] });
+        }
+        self.record_action("updateCard", data);
+        Some(card)
+    }
+
+    fn checklists_with_items(&self, parent_field: &str, parent_id: &str) -> Value {
+        let mut checklists = children(&self.checklists, parent_field, parent_id);
+        for checklist in checklists.as_array_mut().unwrap() {
+            checklist["checkItems"] = children(&self.check_items, "idChecklist", checklist["id"].as_str().unwrap());
+        }
+        checklists
+    }
+
+    fn board_snapshot(&mut self, board_id: &str) -> Response {
+        let mut board = match with_id(&mut self.boards, board_id) {
+            Some(board) => board.clone(),
+            None => return not_found(),
+        };
+        let mut comments: Vec<Value> =
+            self.actions.iter().filter(|action| action["type"] == "commentCard" && action["data"]["board"]["id"] == board_id).cloned().collect();
+        comments.reverse();
+
+        board["labels"] = children(&self.labels, "idBoard", board_id);
+        board["lists"] = children(&self.lists, "idBoard", board_id);
+        board["cards"] = children(&self.cards, "idBoard", board_id);
+        board["checklists"] = self.checklists_with_items("idBoard", board_id);
+        board["actions"] = Value::Array(comments);
+        ok(board)
+    }
+}
@@ -1,8 +1,16 @@
+use mongodb::bson::oid;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 pub mod data_repository;
 mod trello_data_store;
 mod mongo_data_store;
+mod sqlite_data_store;
+mod memory_data_store;
+mod merge;
+mod fixtures;
+#[cfg(test)]
+mod mock_trello;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ID {
@@ -32,7 +40,9 @@ pub struct CardChecklistTask {
     pub _id: ID,
     pub name: String,
     pub is_complete: bool,
-    pub checklist_id: ID
+    pub checklist_id: ID,
+    #[serde(default)]
+    pub local_revision: u64, // Bumped by each edit made here, back to 0 once Trello has them
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -52,6 +62,10 @@ pub struct Card {
     pub label_ids: Vec<ID>,
     pub checklists_ids: Vec<ID>,
     pub list_id: ID,
+    #[serde(default)]
+    pub last_activity_instant_seconds: i64, // Trello's dateLastActivity, 0 for cards it has not seen yet
+    #[serde(default)]
+    pub local_revision: u64, // Bumped by each edit made here, back to 0 once Trello has them
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -73,6 +87,336 @@ pub struct Board {
     pub name: String,
 }
 
+/**
+ * Everything on a board: its labels, its open lists and their cards, and the checklists, tasks and comments of those cards.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    pub board: Board,
+    pub labels: Vec<CardLabel>,
+    pub lists: Vec<BoardList>,
+    pub cards: Vec<Card>,
+    pub checklists: Vec<CardChecklist>,
+    pub tasks: Vec<CardChecklistTask>,
+    pub comments: Vec<CardComment>,
+}
+
+impl BoardSnapshot {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} list(s), {} card(s), {} label(s), {} checklist(s), {} task(s) and {} comment(s)",
+            self.lists.len(),
+            self.cards.len(),
+            self.labels.len(),
+            self.checklists.len(),
+            self.tasks.len(),
+            self.comments.len()
+        )
+    }
+}
+
+/**
+ * An entry of a board's actions feed on Trello, e.g. `createCard` or `updateCheckItemStateOnCard`.
+ * Only the ids of the items it touched are kept; applying it reads those items again.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BoardAction {
+    pub _id: ID,
+    pub action_type: String,
+    pub board_id: ID,
+    pub list_ids: Vec<ID>, // the card's list, or both lists for a card that moved
+    pub card_id: Option<ID>,
+    pub checklist_id: Option<ID>,
+    pub date_instant_seconds: i64,
+}
+
+/**
+ * The schema migrations `DataStore::migrate` ran on a local store, or would run on a dry run.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub from_version: i64,
+    pub to_version: i64,
+    pub dry_run: bool,
+    pub steps: Vec<MigrationStep>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MigrationStep {
+    pub version: i64,
+    pub description: String,
+    pub documents: u64, // documents changed, or that would be on a dry run
+}
+
+impl MigrationStep {
+    pub fn describe(&self) -> String {
+        format!("{}: {} ({} document(s))", self.version, self.description, self.documents)
+    }
+}
+
+/**
+ * A change made while Trello could not be reached, kept in the local store's outbox until it is pushed.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub _id: ID,
+    pub change: OutboxChange,
+    #[serde(default)]
+    pub sequence: u64, // One more than the newest entry in the outbox when this one was added
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum OutboxChange {
+    CreateBoard { board: Board },
+    CreateLabel { label: CardLabel },
+    UpdateLabel { label_id: ID, name: String, color: String },
+    DeleteLabel { label_id: ID },
+    CreateList { list: BoardList },
+    CreateCard { card: Card },
+    /** `base` is the card as it was before the edit, to merge the edit with what changed on Trello since */
+    UpdateCard {
+        card: Card,
+        #[serde(default)]
+        base: Option<Card>,
+    },
+    AddComment { comment: CardComment },
+    CreateChecklist { checklist: CardChecklist },
+    CreateTask { task: CardChecklistTask },
+    UpdateTask {
+        card_id: ID,
+        task: CardChecklistTask,
+        #[serde(default)]
+        base: Option<CardChecklistTask>,
+    },
+}
+
+impl OutboxChange {
+    pub fn describe(&self) -> String {
+        match self {
+            OutboxChange::CreateBoard { board } => format!("create board {}", board.name),
+            OutboxChange::CreateLabel { label } => format!("create label {}", label.name),
+            OutboxChange::UpdateLabel { name, .. } => format!("update label {}", name),
+            OutboxChange::DeleteLabel { .. } => String::from("delete label"),
+            OutboxChange::CreateList { list } => format!("create list {}", list.name),
+            OutboxChange::CreateCard { card } => format!("create card {}", card.name),
+            OutboxChange::UpdateCard { card, .. } => format!("update card {}", card.name),
+            OutboxChange::AddComment { .. } => String::from("add comment"),
+            OutboxChange::CreateChecklist { checklist } => format!("create checklist {}", checklist.name),
+            OutboxChange::CreateTask { task } => format!("create task {}", task.name),
+            OutboxChange::UpdateTask { task, .. } => format!("update task {}", task.name),
+        }
+    }
+
+    /**
+     * The ids Trello needs to know to apply the change, other than the id of an item the change creates.
+     */
+    pub fn referenced_ids(&self) -> Vec<&ID> {
+        match self {
+            OutboxChange::CreateBoard { .. } => vec![],
+            OutboxChange::CreateLabel { label } => vec![&label.board_id],
+            OutboxChange::UpdateLabel { label_id, .. } | OutboxChange::DeleteLabel { label_id } => vec![label_id],
+            OutboxChange::CreateList { list } => vec![&list.board_id],
+            OutboxChange::CreateCard { card } => vec![&card.list_id],
+            OutboxChange::UpdateCard { card, .. } => {
+                let mut ids = vec![&card._id, &card.list_id];
+                ids.extend(card.label_ids.iter());
+                ids
+            }
+            OutboxChange::AddComment { comment } => vec![&comment.card_id],
+            OutboxChange::CreateChecklist { checklist } => vec![&checklist.card_id],
+            OutboxChange::CreateTask { task } => vec![&task.checklist_id],
+            OutboxChange::UpdateTask { card_id, task, .. } => vec![card_id, &task._id, &task.checklist_id],
+        }
+    }
+}
+
+/**
+ * A card or task that was changed both locally and on Trello since it was last synced, in different ways.
+ * `item` holds the merged item: the changes that did not conflict, and Trello's value for each field in `fields`
+ * until the user picks a side for it.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub _id: ID,
+    pub item: ConflictedItem,
+    pub fields: Vec<FieldConflict>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ConflictedItem {
+    Card { card: Card },
+    Task { card_id: ID, task: CardChecklistTask },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FieldConflict {
+    pub field: String,
+    pub local: serde_json::Value,
+    pub remote: serde_json::Value,
+}
+
+impl ConflictedItem {
+    pub fn id(&self) -> &ID {
+        match self {
+            ConflictedItem::Card { card } => &card._id,
+            ConflictedItem::Task { task, .. } => &task._id,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ConflictedItem::Card { card } => format!("card {}", card.name),
+            ConflictedItem::Task { task, .. } => format!("task {}", task.name),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct CachedNames {
+    pub boards: Option<Vec<String>>,
+    pub lists: Option<Vec<String>>,
+    pub cards: Option<Vec<String>>,
+    pub checklists: Option<Vec<String>>,
+    pub labels: Option<Vec<String>>,
+    pub tasks: Option<Vec<String>>,
+}
+
+/**
+ * An item a local store keeps: its ids, the id of the item it belongs to and the name
+ * of the table (or collection) items of its kind live in.
+ */
+pub(crate) trait StoredItem: Clone + Serialize + DeserializeOwned {
+    const TABLE: &'static str;
+    fn id(&self) -> &ID;
+    fn id_mut(&mut self) -> &mut ID;
+    fn parent_id(&self) -> Option<&ID>;
+}
+
+impl StoredItem for Board {
+    const TABLE: &'static str = "boards";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { None }
+}
+
+impl StoredItem for CardLabel {
+    const TABLE: &'static str = "labels";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { Some(&self.board_id) }
+}
+
+impl StoredItem for BoardList {
+    const TABLE: &'static str = "lists";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { Some(&self.board_id) }
+}
+
+impl StoredItem for Card {
+    const TABLE: &'static str = "cards";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { Some(&self.list_id) }
+}
+
+impl StoredItem for CardComment {
+    const TABLE: &'static str = "comments";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { Some(&self.card_id) }
+}
+
+impl StoredItem for CardChecklist {
+    const TABLE: &'static str = "checklists";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { Some(&self.card_id) }
+}
+
+impl StoredItem for CardChecklistTask {
+    const TABLE: &'static str = "tasks";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { Some(&self.checklist_id) }
+}
+
+impl StoredItem for BoardAction {
+    const TABLE: &'static str = "actions";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { Some(&self.board_id) }
+}
+
+impl StoredItem for OutboxEntry {
+    const TABLE: &'static str = "outbox";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { None }
+}
+
+impl StoredItem for SyncConflict {
+    const TABLE: &'static str = "conflicts";
+    fn id(&self) -> &ID { &self._id }
+    fn id_mut(&mut self) -> &mut ID { &mut self._id }
+    fn parent_id(&self) -> Option<&ID> { None }
+}
+
+/**
+ * Set by a local store on items a sync no longer got from Trello (deleted or archived there),
+ * to the instant in seconds they went missing. Reads skip such items until they are purged.
+ */
+pub(crate) const TOMBSTONE_FIELD: &str = "tombstoned_instant_seconds";
+
+/**
+ * The tables of items that belong to another item: the table, the field with the parent's id and the parent's table.
+ * Parents come before their children, so purging in this order also removes items whose parent was purged.
+ */
+pub(crate) const CHILD_TABLES: [(&str, &str, &str); 6] = [
+    ("labels", "board_id", "boards"),
+    ("lists", "board_id", "boards"),
+    ("cards", "list_id", "lists"),
+    ("comments", "card_id", "cards"),
+    ("checklists", "card_id", "cards"),
+    ("tasks", "checklist_id", "checklists"),
+];
+
+/**
+ * Sets `trello_id` on every id in `value` with the given local id that does not have one yet,
+ * so items created offline and everything referring to them pick up the id Trello gave them.
+ * Returns whether anything changed.
+ */
+pub(crate) fn fill_trello_id(value: &mut serde_json::Value, local_id: &str, trello_id: &str) -> bool {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut changed = false;
+            let is_match = object.get("local_id").and_then(|id| id.as_str()) == Some(local_id)
+                && object.get("trello_id").unwrap_or(&serde_json::Value::Null).is_null();
+            if is_match {
+                object.insert(String::from("trello_id"), serde_json::Value::from(trello_id));
+                changed = true;
+            }
+
+            for field in object.values_mut() {
+                changed |= fill_trello_id(field, local_id, trello_id);
+            }
+            changed
+        }
+        serde_json::Value::Array(items) => {
+            let mut changed = false;
+            for item in items {
+                changed |= fill_trello_id(item, local_id, trello_id);
+            }
+            changed
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn new_local_id() -> Option<String> {
+    Some(oid::ObjectId::new().to_hex())
+}
+
 #[derive(Debug)]
 pub struct NotImplError {}
 
@@ -84,6 +428,63 @@ impl std::fmt::Display for NotImplError {
     }
 }
 
+/**
+ * Returned by a remote store when it could not be reached at all, as opposed to rejecting the request.
+ */
+#[derive(Debug)]
+pub struct RemoteUnavailableError {}
+
+impl std::error::Error for RemoteUnavailableError {}
+
+impl std::fmt::Display for RemoteUnavailableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Trello could not be reached")
+    }
+}
+
+/**
+ * Returned by the Trello store when Trello kept rate limiting a request or failing it with a server error,
+ * and it was retried as many times as the store retries.
+ */
+#[derive(Debug)]
+pub struct TooManyRetriesError {
+    status: u16,
+    retries: u32,
+}
+
+impl std::error::Error for TooManyRetriesError {}
+
+impl std::fmt::Display for TooManyRetriesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.status == 429 {
+            write!(f, "Trello is limiting how many requests are sent and still was after {} retries. Wait a few seconds and try again", self.retries)
+        } else {
+            write!(f, "Trello failed with status {} and still did after {} retries. Try again later", self.status, self.retries)
+        }
+    }
+}
+
+/**
+ * Returned by the Trello store when Trello answered a request with an error status, with the body it sent.
+ */
+#[derive(Debug)]
+pub struct TrelloResponseError {
+    status: u16,
+    message: String,
+}
+
+impl std::error::Error for TrelloResponseError {}
+
+impl std::fmt::Display for TrelloResponseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.status {
+            401 => write!(f, "Trello did not accept the developer API key and token: {}", self.message.trim()),
+            404 => write!(f, "Trello has no such item, it may have been deleted: {}", self.message.trim()),
+            _ => write!(f, "Trello refused the request with status {}: {}", self.status, self.message.trim()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct InvalidInputError {
     message: Option<String>
@@ -0,0 +1,687 @@
+use crate::data::data_repository::DataStore;
+use crate::data::*;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const DEFAULT_DB_FILE: &str = "trello-data.sqlite3";
+const TABLES: [&str; 9] = ["boards", "labels", "lists", "cards", "comments", "checklists", "tasks", "outbox", "conflicts"];
+
+/**
+ * Local store that keeps everything in a single SQLite file.
+ * Each table holds one kind of item as JSON, next to the columns needed to look items up:
+ * the item's own ids and the ids of the item it belongs to (e.g. the board of a list).
+ */
+pub struct SqliteDataStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteDataStore {
+    /**
+     * Opens the database at `sqlite.path` from the config, or `trello-data.sqlite3` in the app's config folder.
+     */
+    pub fn new(config: &Option<Value>) -> Result<SqliteDataStore, Box<dyn std::error::Error>> {
+        let configured_path = config
+            .as_ref()
+            .and_then(|config| config.get("sqlite"))
+            .and_then(|sqlite| sqlite.get("path"))
+            .and_then(|path| path.as_str());
+
+        let path = match configured_path {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let mut path = dirs::config_dir().unwrap_or_default();
+                path.push("trello-cli");
+                path.push(DEFAULT_DB_FILE);
+                path
+            }
+        };
+
+        SqliteDataStore::with_connection(Connection::open(path)?)
+    }
+
+    pub fn with_connection(connection: Connection) -> Result<SqliteDataStore, Box<dyn std::error::Error>> {
+        for table in TABLES {
+            connection.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {table} (
+                        local_id TEXT PRIMARY KEY,
+                        trello_id TEXT,
+                        parent_local_id TEXT,
+                        parent_trello_id TEXT,
+                        data TEXT NOT NULL,
+                        tombstoned_instant_seconds INTEGER
+                    )",
+                    table = table
+                ),
+                [],
+            )?;
+            let has_tombstones: bool = connection.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = 'tombstoned_instant_seconds'",
+                params![table],
+                |row| row.get(0),
+            )?;
+            if !has_tombstones {
+                connection.execute(&format!("ALTER TABLE {} ADD COLUMN tombstoned_instant_seconds INTEGER", table), [])?;
+            }
+            connection.execute(
+                &format!(
+                    "CREATE INDEX IF NOT EXISTS {table}_parent ON {table} (parent_local_id, parent_trello_id)",
+                    table = table
+                ),
+                [],
+            )?;
+        }
+
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                key TEXT PRIMARY KEY,
+                last_synced_instant_seconds INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS sync_cursors (
+                key TEXT PRIMARY KEY,
+                cursor TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(SqliteDataStore { connection: Mutex::new(connection) })
+    }
+
+    /**
+     * Items whose parent has either the local or the Trello id of `parent_id`, since items read from
+     * Trello only know their parent's Trello id. Without a parent this returns the top level items.
+     * Tombstoned items are left out.
+     */
+    fn find<T: StoredItem>(connection: &Connection, parent_id: Option<&ID>) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let parent_local_id = parent_id.and_then(|id| id.local_id.clone());
+        let parent_trello_id = parent_id.and_then(|id| id.trello_id.clone());
+        let mut statement = connection.prepare(&format!(
+            "SELECT data FROM {} WHERE tombstoned_instant_seconds IS NULL AND (parent_local_id = ?1 OR parent_trello_id = ?2
+                OR (?1 IS NULL AND ?2 IS NULL AND parent_local_id IS NULL AND parent_trello_id IS NULL))
+                ORDER BY rowid",
+            T::TABLE
+        ))?;
+        let rows = statement.query_map(params![parent_local_id, parent_trello_id], |row| row.get::<_, String>(0))?;
+
+        let mut items: Vec<T> = vec![];
+        for data in rows {
+            items.push(serde_json::from_str(&data?)?);
+        }
+        Ok(items)
+    }
+
+    fn find_by_id<T: StoredItem>(connection: &Connection, id: &ID) -> Result<Option<T>, Box<dyn std::error::Error>> {
+        let data: Option<String> = match &id.local_id {
+            Some(local_id) => connection
+                .query_row(&format!("SELECT data FROM {} WHERE local_id = ?1", T::TABLE), params![local_id], |row| row.get(0))
+                .optional()?,
+            None => connection
+                .query_row(&format!("SELECT data FROM {} WHERE trello_id = ?1", T::TABLE), params![id.trello_id], |row| row.get(0))
+                .optional()?,
+        };
+
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /**
+     * Inserts `item`, or updates the stored item with the same local id in place, so it keeps its place in the table.
+     */
+    fn save<T: StoredItem>(connection: &Connection, item: &T) -> Result<(), Box<dyn std::error::Error>> {
+        let parent_id = item.parent_id();
+        connection.execute(
+            &format!(
+                "INSERT INTO {} (local_id, trello_id, parent_local_id, parent_trello_id, data) VALUES (?1, ?2, ?3, ?4, ?5)
+                    ON CONFLICT(local_id) DO UPDATE SET trello_id = excluded.trello_id, parent_local_id = excluded.parent_local_id,
+                    parent_trello_id = excluded.parent_trello_id, data = excluded.data, tombstoned_instant_seconds = NULL",
+                T::TABLE
+            ),
+            params![
+                item.id().local_id,
+                item.id().trello_id,
+                parent_id.and_then(|id| id.local_id.clone()),
+                parent_id.and_then(|id| id.trello_id.clone()),
+                serde_json::to_string(item)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn delete<T: StoredItem>(connection: &Connection, id: &ID) -> Result<(), Box<dyn std::error::Error>> {
+        match &id.local_id {
+            Some(local_id) => connection.execute(&format!("DELETE FROM {} WHERE local_id = ?1", T::TABLE), params![local_id])?,
+            None => connection.execute(&format!("DELETE FROM {} WHERE trello_id = ?1", T::TABLE), params![id.trello_id])?,
+        };
+        Ok(())
+    }
+
+    fn create<T: StoredItem>(&self, mut item: T) -> Result<T, Box<dyn std::error::Error>> {
+        item.id_mut().local_id = new_local_id();
+        SqliteDataStore::save(&self.connection.lock().unwrap(), &item)?;
+        Ok(item)
+    }
+
+    fn update<T: StoredItem>(&self, item: &T) -> Result<T, Box<dyn std::error::Error>> {
+        let connection = self.connection.lock().unwrap();
+        let mut updated: T = item.clone();
+        if updated.id().local_id.is_none() {
+            let existing: Option<T> = SqliteDataStore::find_by_id(&connection, item.id())?;
+            updated.id_mut().local_id = existing.and_then(|existing| existing.id().local_id.clone()).or_else(new_local_id);
+        }
+        SqliteDataStore::save(&connection, &updated)?;
+        Ok(updated)
+    }
+
+    /**
+     * Same upsert semantics as the MongoDB store: items that already exist (matched by Trello id)
+     * keep their local id and are updated, the rest are inserted with a new one. Items under `parent_id`
+     * that Trello no longer returned are tombstoned, and the ones that were created offline and not
+     * pushed yet are returned after the synced ones.
+     */
+    fn sync<T: StoredItem>(&self, parent_id: Option<&ID>, remote_items: Vec<T>) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+
+        let existing_items: Vec<T> = SqliteDataStore::find(&transaction, parent_id)?;
+        let mut missing_local_id_by_trello_id: HashMap<String, Option<String>> = HashMap::new();
+        let mut unpushed_items: Vec<T> = vec![];
+        for item in existing_items {
+            match item.id().trello_id.clone() {
+                Some(trello_id) => {
+                    missing_local_id_by_trello_id.insert(trello_id, item.id().local_id.clone());
+                }
+                None => unpushed_items.push(item),
+            }
+        }
+
+        let mut items_with_ids: Vec<T> = vec![];
+        for mut item in remote_items {
+            // Also matches items that moved here from another parent or were tombstoned before
+            let lookup_id = ID { trello_id: item.id().trello_id.clone(), local_id: None };
+            let existing: Option<T> = SqliteDataStore::find_by_id(&transaction, &lookup_id)?;
+            item.id_mut().local_id = existing.and_then(|existing| existing.id().local_id.clone()).or_else(new_local_id);
+            SqliteDataStore::save(&transaction, &item)?;
+            if let Some(trello_id) = &item.id().trello_id {
+                missing_local_id_by_trello_id.remove(trello_id);
+            }
+            items_with_ids.push(item);
+        }
+
+        let tombstoned_instant_seconds = Utc::now().timestamp();
+        for local_id in missing_local_id_by_trello_id.into_values() {
+            transaction.execute(
+                &format!("UPDATE {} SET tombstoned_instant_seconds = ?2 WHERE local_id = ?1", T::TABLE),
+                params![local_id, tombstoned_instant_seconds],
+            )?;
+        }
+
+        transaction.commit()?;
+        items_with_ids.extend(unpushed_items);
+        Ok(items_with_ids)
+    }
+
+    fn purge(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+
+        let mut purged: usize = 0;
+        for table in ["boards", "labels", "lists", "cards", "comments", "checklists", "tasks"] {
+            purged += transaction.execute(&format!("DELETE FROM {} WHERE tombstoned_instant_seconds IS NOT NULL", table), [])?;
+        }
+        for (table, _, parent_table) in CHILD_TABLES {
+            purged += transaction.execute(
+                &format!(
+                    "DELETE FROM {table} WHERE NOT EXISTS (
+                        SELECT 1 FROM {parent_table} AS parent
+                        WHERE parent.local_id = {table}.parent_local_id OR parent.trello_id = {table}.parent_trello_id
+                    )",
+                    table = table,
+                    parent_table = parent_table
+                ),
+                [],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(purged)
+    }
+
+    fn fill_trello_ids(&self, local_id: &str, trello_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+
+        for table in TABLES {
+            let mut changed_rows: Vec<(String, String)> = vec![];
+            {
+                let mut statement = transaction.prepare(&format!("SELECT local_id, data FROM {} WHERE instr(data, ?1) > 0", table))?;
+                let rows = statement.query_map(params![local_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+                for row in rows {
+                    let (row_local_id, data) = row?;
+                    let mut item: Value = serde_json::from_str(&data)?;
+                    if fill_trello_id(&mut item, local_id, trello_id) {
+                        changed_rows.push((row_local_id, serde_json::to_string(&item)?));
+                    }
+                }
+            }
+
+            for (row_local_id, data) in changed_rows {
+                transaction.execute(&format!("UPDATE {} SET data = ?2 WHERE local_id = ?1", table), params![row_local_id, data])?;
+            }
+            transaction.execute(
+                &format!("UPDATE {} SET trello_id = ?2 WHERE local_id = ?1 AND trello_id IS NULL", table),
+                params![local_id, trello_id],
+            )?;
+            transaction.execute(
+                &format!("UPDATE {} SET parent_trello_id = ?2 WHERE parent_local_id = ?1 AND parent_trello_id IS NULL", table),
+                params![local_id, trello_id],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn get<T: StoredItem>(&self, parent_id: Option<&ID>) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        SqliteDataStore::find(&self.connection.lock().unwrap(), parent_id)
+    }
+}
+
+#[async_trait]
+impl DataStore for SqliteDataStore {
+    async fn get_all_boards(&self) -> Result<Vec<Board>, Box<dyn std::error::Error>> {
+        self.get(None)
+    }
+
+    async fn create_board(&self, name: &str, trello_id: Option<String>) -> Result<Board, Box<dyn std::error::Error>> {
+        self.create(Board {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+        })
+    }
+
+    async fn get_all_board_labels(&self, board_id: ID) -> Result<Vec<CardLabel>, Box<dyn std::error::Error>> {
+        self.get(Some(&board_id))
+    }
+
+    async fn delete_board_label(&self, label_id: ID) -> Result<(), Box<dyn std::error::Error>> {
+        SqliteDataStore::delete::<CardLabel>(&self.connection.lock().unwrap(), &label_id)
+    }
+
+    async fn update_board_label(&self, label_id: ID, name: &str, color: &str) -> Result<CardLabel, Box<dyn std::error::Error>> {
+        let existing: Option<CardLabel> = SqliteDataStore::find_by_id(&self.connection.lock().unwrap(), &label_id)?;
+        match existing {
+            Some(mut label) => {
+                label.name = name.to_string();
+                label.color = color.to_string();
+                self.update(&label)
+            }
+            None => Err(Box::new(InvalidInputError { message: Some(String::from("Could not find the label to update")) })),
+        }
+    }
+
+    async fn create_board_label(&self, board_id: ID, name: &str, color: &str, trello_id: Option<String>) -> Result<CardLabel, Box<dyn std::error::Error>> {
+        self.create(CardLabel {
+            _id: ID { trello_id, local_id: None },
+            board_id,
+            name: name.to_string(),
+            color: color.to_string(),
+        })
+    }
+
+    async fn get_all_board_lists(&self, board_id: ID) -> Result<Vec<BoardList>, Box<dyn std::error::Error>> {
+        self.get(Some(&board_id))
+    }
+
+    async fn create_board_list(&self, board_id: ID, name: &str, trello_id: Option<String>) -> Result<BoardList, Box<dyn std::error::Error>> {
+        self.create(BoardList {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+            board_id,
+        })
+    }
+
+    async fn get_all_list_cards(&self, list_id: ID) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        self.get(Some(&list_id))
+    }
+
+    async fn get_card(&self, card_id: ID) -> Result<Card, Box<dyn std::error::Error>> {
+        match SqliteDataStore::find_by_id(&self.connection.lock().unwrap(), &card_id)? {
+            Some(card) => Ok(card),
+            None => Err(Box::new(InvalidInputError { message: Some(String::from("Could not find the card")) })),
+        }
+    }
+
+    async fn create_list_card(&self, list_id: ID, name: &str, trello_id: Option<String>) -> Result<Card, Box<dyn std::error::Error>> {
+        self.create(Card {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+            description: String::from(""),
+            due_date_instant_seconds: 0,
+            due_complete: false,
+            label_ids: vec![],
+            checklists_ids: vec![],
+            list_id,
+            last_activity_instant_seconds: 0,
+            local_revision: 0,
+        })
+    }
+
+    async fn update_card(&self, card: &Card) -> Result<Card, Box<dyn std::error::Error>> {
+        self.update(card)
+    }
+
+    async fn get_card_comments(&self, card_id: ID) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
+        self.get(Some(&card_id))
+    }
+
+    async fn add_card_comment(&self, card_id: ID, text: &str, trello_id: Option<String>) -> Result<CardComment, Box<dyn std::error::Error>> {
+        self.create(CardComment {
+            _id: ID { trello_id, local_id: None },
+            text: text.to_string(),
+            commenter_name: String::from(""),
+            comment_time_instant_seconds: 0,
+            card_id,
+        })
+    }
+
+    async fn get_card_checklists(&self, card_id: ID) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
+        self.get(Some(&card_id))
+    }
+
+    async fn create_card_checklist(&self, card_id: ID, name: &str, trello_id: Option<String>) -> Result<CardChecklist, Box<dyn std::error::Error>> {
+        self.create(CardChecklist {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+            card_id,
+        })
+    }
+
+    async fn get_checklist_tasks(&self, checklist_id: ID) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        self.get(Some(&checklist_id))
+    }
+
+    async fn create_checklist_task(&self, checklist_id: ID, name: &str, trello_id: Option<String>) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
+        self.create(CardChecklistTask {
+            _id: ID { trello_id, local_id: None },
+            name: name.to_string(),
+            is_complete: false,
+            checklist_id,
+            local_revision: 0,
+        })
+    }
+
+    async fn update_checklist_task(&self, _card_id: ID, task: &CardChecklistTask) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
+        self.update(task)
+    }
+
+    async fn sync_boards(&self, boards: Vec<Board>) -> Result<Vec<Board>, Box<dyn std::error::Error>> {
+        self.sync(None, boards)
+    }
+
+    async fn sync_labels(&self, board_id: ID, labels: Vec<CardLabel>) -> Result<Vec<CardLabel>, Box<dyn std::error::Error>> {
+        self.sync(Some(&board_id), labels)
+    }
+
+    async fn sync_lists(&self, board_id: ID, lists: Vec<BoardList>) -> Result<Vec<BoardList>, Box<dyn std::error::Error>> {
+        self.sync(Some(&board_id), lists)
+    }
+
+    async fn sync_cards(&self, list_id: ID, cards: Vec<Card>) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        self.sync(Some(&list_id), cards)
+    }
+
+    async fn sync_checklists(&self, card_id: ID, checklists: Vec<CardChecklist>) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
+        self.sync(Some(&card_id), checklists)
+    }
+
+    async fn sync_tasks(&self, checklist_id: ID, tasks: Vec<CardChecklistTask>) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        self.sync(Some(&checklist_id), tasks)
+    }
+
+    async fn sync_comments(&self, card_id: ID, comments: Vec<CardComment>) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
+        self.sync(Some(&card_id), comments)
+    }
+
+    async fn add_to_outbox(&self, entry: &OutboxEntry) -> Result<(), Box<dyn std::error::Error>> {
+        SqliteDataStore::save(&self.connection.lock().unwrap(), entry)
+    }
+
+    async fn get_outbox(&self) -> Result<Vec<OutboxEntry>, Box<dyn std::error::Error>> {
+        // Stable, so entries queued before they had a sequence keep the order they were added in
+        let mut entries: Vec<OutboxEntry> = self.get(None)?;
+        entries.sort_by_key(|entry| entry.sequence);
+        Ok(entries)
+    }
+
+    async fn remove_from_outbox(&self, entry_id: &ID) -> Result<(), Box<dyn std::error::Error>> {
+        SqliteDataStore::delete::<OutboxEntry>(&self.connection.lock().unwrap(), entry_id)
+    }
+
+    async fn set_trello_id(&self, local_id: &str, trello_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.fill_trello_ids(local_id, trello_id)
+    }
+
+    async fn set_last_synced(&self, key: &str, instant_seconds: i64) -> Result<(), Box<dyn std::error::Error>> {
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO sync_state (key, last_synced_instant_seconds) VALUES (?1, ?2)",
+            params![key, instant_seconds],
+        )?;
+        Ok(())
+    }
+
+    async fn get_last_synced(&self, key: &str) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        let last_synced: Option<i64> = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row("SELECT last_synced_instant_seconds FROM sync_state WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()?;
+        Ok(last_synced)
+    }
+
+    async fn set_sync_cursor(&self, key: &str, cursor: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO sync_cursors (key, cursor) VALUES (?1, ?2)",
+            params![key, cursor],
+        )?;
+        Ok(())
+    }
+
+    async fn get_sync_cursor(&self, key: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let cursor: Option<String> = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row("SELECT cursor FROM sync_cursors WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()?;
+        Ok(cursor)
+    }
+
+    async fn save_conflict(&self, conflict: &SyncConflict) -> Result<(), Box<dyn std::error::Error>> {
+        let connection = self.connection.lock().unwrap();
+        // Updated in place rather than replaced, so the conflict keeps its position
+        let updated = connection.execute(
+            "UPDATE conflicts SET data = ?2 WHERE local_id = ?1",
+            params![conflict._id.local_id, serde_json::to_string(conflict)?],
+        )?;
+        if updated == 0 {
+            SqliteDataStore::save(&connection, conflict)?;
+        }
+        Ok(())
+    }
+
+    async fn get_conflicts(&self) -> Result<Vec<SyncConflict>, Box<dyn std::error::Error>> {
+        self.get(None)
+    }
+
+    async fn remove_conflict(&self, conflict_id: &ID) -> Result<(), Box<dyn std::error::Error>> {
+        SqliteDataStore::delete::<SyncConflict>(&self.connection.lock().unwrap(), conflict_id)
+    }
+
+    async fn purge_tombstones(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        self.purge()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> SqliteDataStore {
+        SqliteDataStore::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn trello_id(id: &str) -> ID {
+        ID { trello_id: Some(id.to_string()), local_id: None }
+    }
+
+    #[tokio::test]
+    async fn sync_keeps_local_ids_of_existing_items() {
+        let store = store();
+        let synced = store
+            .sync_boards(vec![Board { _id: trello_id("b1"), name: String::from("Eng") }])
+            .await
+            .unwrap();
+        let local_id = synced[0]._id.local_id.clone();
+        assert!(local_id.is_some());
+
+        let resynced = store
+            .sync_boards(vec![
+                Board { _id: trello_id("b1"), name: String::from("Engineering") },
+                Board { _id: trello_id("b2"), name: String::from("Ops") },
+            ])
+            .await
+            .unwrap();
+        assert_eq!(resynced[0]._id.local_id, local_id);
+
+        let boards = store.get_all_boards().await.unwrap();
+        let names: Vec<&str> = boards.iter().map(|board| board.name.as_str()).collect();
+        assert_eq!(names, vec!["Engineering", "Ops"]);
+    }
+
+    #[tokio::test]
+    async fn items_are_found_by_parent() {
+        let store = store();
+        let board = store.create_board("Eng", Some(String::from("b1"))).await.unwrap();
+        let todo = store.create_board_list(board._id.clone(), "Todo", None).await.unwrap();
+        store.create_board_list(board._id.clone(), "Done", None).await.unwrap();
+        store
+            .sync_lists(board._id.clone(), vec![BoardList { _id: trello_id("l1"), name: String::from("Review"), board_id: trello_id("b1") }])
+            .await
+            .unwrap();
+
+        let mut card = store.create_list_card(todo._id.clone(), "Fix login", None).await.unwrap();
+        card.description = String::from("Steps to reproduce");
+        store.update_card(&card).await.unwrap();
+
+        assert_eq!(store.get_all_board_lists(board._id.clone()).await.unwrap().len(), 3);
+        let cards = store.get_all_list_cards(todo._id).await.unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].description, "Steps to reproduce");
+
+        let label = store.create_board_label(board._id.clone(), "Bug", "red", None).await.unwrap();
+        store.update_board_label(label._id.clone(), "Defect", "orange").await.unwrap();
+        assert_eq!(store.get_all_board_labels(board._id.clone()).await.unwrap()[0].name, "Defect");
+        store.delete_board_label(label._id).await.unwrap();
+        assert!(store.get_all_board_labels(board._id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn updated_items_keep_their_order() {
+        let store = store();
+        let board = store.create_board("Eng", None).await.unwrap();
+        let bug = store.create_board_label(board._id.clone(), "Bug", "red", None).await.unwrap();
+        store.create_board_label(board._id.clone(), "Feature", "green", None).await.unwrap();
+        store.update_board_label(bug._id, "Defect", "red").await.unwrap();
+
+        let names: Vec<String> = store.get_all_board_labels(board._id).await.unwrap().into_iter().map(|label| label.name).collect();
+        assert_eq!(names, vec!["Defect", "Feature"]);
+    }
+
+    #[tokio::test]
+    async fn items_missing_from_trello_are_tombstoned_then_purged() {
+        let store = store();
+        let board = store.sync_boards(vec![Board { _id: trello_id("b1"), name: String::from("Eng") }]).await.unwrap().remove(0);
+        let lists = vec![
+            BoardList { _id: trello_id("l1"), name: String::from("Todo"), board_id: trello_id("b1") },
+            BoardList { _id: trello_id("l2"), name: String::from("Done"), board_id: trello_id("b1") },
+        ];
+        store.sync_lists(board._id.clone(), lists.clone()).await.unwrap();
+        store.create_list_card(trello_id("l2"), "Fix login", None).await.unwrap();
+
+        store.sync_lists(board._id.clone(), vec![lists[0].clone()]).await.unwrap();
+        let names: Vec<String> = store.get_all_board_lists(board._id.clone()).await.unwrap().into_iter().map(|list| list.name).collect();
+        assert_eq!(names, vec!["Todo"]);
+
+        // The list and the card that was in it
+        assert_eq!(store.purge_tombstones().await.unwrap(), 2);
+        assert!(store.get_all_list_cards(trello_id("l2")).await.unwrap().is_empty());
+
+        // An item that comes back keeps its local id
+        let todo = store.get_all_board_lists(board._id.clone()).await.unwrap().remove(0);
+        store.sync_lists(board._id.clone(), vec![]).await.unwrap();
+        let restored = store.sync_lists(board._id, vec![lists[0].clone()]).await.unwrap();
+        assert_eq!(restored[0]._id.local_id, todo._id.local_id);
+    }
+
+    #[tokio::test]
+    async fn trello_ids_reach_items_and_pending_changes() {
+        let store = store();
+        let board = store.create_board("Eng", None).await.unwrap();
+        let list = store.create_board_list(board._id.clone(), "Todo", None).await.unwrap();
+        let entry = OutboxEntry {
+            _id: ID { trello_id: None, local_id: new_local_id() },
+            change: OutboxChange::CreateList { list },
+            sequence: 1,
+        };
+        store.add_to_outbox(&entry).await.unwrap();
+
+        let board_local_id = board._id.local_id.clone().unwrap();
+        store.set_trello_id(&board_local_id, "b1").await.unwrap();
+
+        assert_eq!(store.get_all_boards().await.unwrap()[0]._id.trello_id, Some(String::from("b1")));
+        let lists = store.get_all_board_lists(trello_id("b1")).await.unwrap();
+        assert_eq!(lists[0].board_id.trello_id, Some(String::from("b1")));
+        match &store.get_outbox().await.unwrap()[0].change {
+            OutboxChange::CreateList { list } => assert_eq!(list.board_id.trello_id, Some(String::from("b1"))),
+            _ => panic!("Unexpected outbox entry"),
+        }
+
+        store.remove_from_outbox(&entry._id).await.unwrap();
+        assert!(store.get_outbox().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn outbox_is_read_in_sequence() {
+        let store = store();
+        for (sequence, name) in [(2, "Later"), (1, "Earlier")] {
+            let board = Board { _id: ID { trello_id: None, local_id: new_local_id() }, name: name.to_string() };
+            let entry = OutboxEntry { _id: ID { trello_id: None, local_id: new_local_id() }, change: OutboxChange::CreateBoard { board }, sequence };
+            store.add_to_outbox(&entry).await.unwrap();
+        }
+
+        let names: Vec<String> = store
+            .get_outbox()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| match entry.change {
+                OutboxChange::CreateBoard { board } => board.name,
+                _ => panic!("Unexpected outbox entry"),
+            })
+            .collect();
+        assert_eq!(names, vec!["Earlier", "Later"]);
+    }
+}
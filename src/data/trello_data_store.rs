@@ -1,50 +1,278 @@
 use std::fs::File;
 use std::io::Read;
-use std::sync::Once;
 
 use crate::data::data_repository::DataStore;
+use crate::data::fixtures::{FixtureMode, Fixtures};
 use crate::data::*;
 
 use async_trait::async_trait;
-use reqwest;
+use reqwest::header::{AUTHORIZATION, RETRY_AFTER};
+use reqwest::{self, Method, StatusCode};
 use serde_json::{Value, json};
 use chrono::{DateTime, TimeZone, Utc};
 
-static START: Once = Once::new();
-
-const URL_BASE: &str = "https://api.trello.com/1";
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/** Where Trello's REST API is, unless `trello.api_url` in the config points somewhere else */
+const DEFAULT_URL_BASE: &str = "https://api.trello.com/1";
+/** The most comment actions Trello returns nested in a board */
+const BOARD_COMMENTS_LIMIT: usize = 1000;
+/** The most comment actions Trello returns for a card */
+const CARD_COMMENTS_LIMIT: usize = 1000;
+/** The most actions Trello returns from a board's actions feed in one page */
+const BOARD_ACTIONS_LIMIT: usize = 1000;
+/** How long to wait for a connection to Trello, unless `trello.connect_timeout_seconds` is set */
+const DEFAULT_CONNECT_TIMEOUT_SECONDS: f64 = 10.0;
+/** How long a whole request may take, unless `trello.timeout_seconds` is set */
+const DEFAULT_TIMEOUT_SECONDS: f64 = 30.0;
+/** How long an idle connection is kept open for the next request */
+const POOL_IDLE_TIMEOUT_SECONDS: u64 = 90;
+/** Trello allows each token 100 requests in any 10 seconds */
+const RATE_LIMIT_REQUESTS: f64 = 100.0;
+const RATE_LIMIT_SECONDS: f64 = 10.0;
+/** How many times a request Trello rate limited or failed with a server error is sent again */
+const MAX_RETRIES: u32 = 4;
+/** The wait before the first retry, doubled for every retry after it */
+const RETRY_BASE_DELAY_MILLIS: u64 = 500;
+const USER_AGENT: &str = concat!("trello-cli/", env!("CARGO_PKG_VERSION"));
 const PATH_TO_KEY: &str = ".config/developer_api_key.txt";
 const PATH_TO_TOKEN: &str = ".config/developer_api_token.txt";
 
-static mut key: Option<String> = None;
-static mut token: Option<String> = None;
+#[derive(Clone)]
+pub struct TrelloDataStore {
+    url_base: String,
+    key: String,
+    token: String,
+    client: reqwest::Client,
+    rate_limiter: RateLimiter,
+    retry_base_delay: Duration,
+    fixtures: Option<Arc<Fixtures>>,
+}
 
+/**
+ * Token bucket that spaces requests out to stay under Trello's rate limit instead of running into it.
+ * A request that finds the bucket empty waits for its token. Clones share the same bucket.
+ */
 #[derive(Clone)]
-pub struct TrelloDataStore {}
+struct RateLimiter {
+    capacity: f64,
+    tokens_per_second: f64,
+    bucket: Arc<Mutex<(f64, Instant)>>,
+}
+
+impl RateLimiter {
+    fn new(requests: f64, seconds: f64) -> RateLimiter {
+        RateLimiter { capacity: requests, tokens_per_second: requests / seconds, bucket: Arc::new(Mutex::new((requests, Instant::now()))) }
+    }
+
+    async fn acquire(&self) {
+        let wait_seconds = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let (tokens, refilled_at) = *bucket;
+            let now = Instant::now();
+            // Tokens can go below zero, a request that takes one then waits until it would have been refilled
+            let tokens = (tokens + now.duration_since(refilled_at).as_secs_f64() * self.tokens_per_second).min(self.capacity) - 1.0;
+            *bucket = (tokens, now);
+            -tokens / self.tokens_per_second
+        };
+        if wait_seconds > 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(wait_seconds)).await;
+        }
+    }
+}
+
+/**
+ * The wait before retry number `retry` (from 0): exponential, plus up to half again as much at random
+ * so that clients rate limited together do not all retry at the same moment.
+ */
+fn retry_delay(base_delay: Duration, retry: u32) -> Duration {
+    let delay = base_delay * 2_u32.pow(retry);
+    let random = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|since_epoch| since_epoch.subsec_nanos()).unwrap_or(0);
+    delay + delay.mul_f64(f64::from(random % 1000) / 2000.0)
+}
+
+/**
+ * How long a `Retry-After` header asks to wait, given either as seconds or as an HTTP date.
+ */
+fn retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let retry_at = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some((retry_at.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or_default())
+}
+
+/** What Trello answered, or what a fixture recorded it answering */
+struct TrelloResponse {
+    status: StatusCode,
+    body: String,
+    retry_after: Option<Duration>,
+}
+
+/**
+ * A request to Trello's API, relative to its base URL. Path segments and query parameters are percent-encoded
+ * when it is sent. The key and token are not part of it, they go in the Authorization header.
+ */
+struct TrelloRequest {
+    method: Method,
+    segments: Vec<String>,
+    query: Vec<(&'static str, String)>,
+    body: Option<Value>,
+}
+
+impl TrelloRequest {
+    fn new(method: Method, segments: &[&str]) -> TrelloRequest {
+        TrelloRequest {
+            method,
+            segments: segments.iter().map(|segment| segment.to_string()).collect(),
+            query: vec![],
+            body: None,
+        }
+    }
+
+    fn query(mut self, name: &'static str, value: impl ToString) -> TrelloRequest {
+        self.query.push((name, value.to_string()));
+        self
+    }
+
+    fn json(mut self, body: Value) -> TrelloRequest {
+        self.body = Some(body);
+        self
+    }
+
+    /**
+     * The encoded path and query, e.g. `/boards/abc/labels?name=In%20Progress`.
+     */
+    fn url_path(&self) -> String {
+        let mut url_path: String = self.segments.iter().map(|segment| format!("/{}", encode(segment))).collect();
+        for (index, (name, value)) in self.query.iter().enumerate() {
+            url_path.push(if index == 0 { '?' } else { '&' });
+            url_path.push_str(&format!("{}={}", encode(name), encode(value)));
+        }
+        url_path
+    }
+}
+
+/**
+ * Percent-encodes everything but the characters that never need it in a URL, so a name with `&`, `#`, `+`
+ * or non-ASCII characters reaches Trello as it was typed.
+ */
+fn encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
 
 impl TrelloDataStore {
-    pub fn init(config: Option<Value>) {
-        let mut _key = String::from("");
-        let mut _token = String::from("");
+    pub fn new(config: &Option<Value>) -> Result<TrelloDataStore, Box<dyn std::error::Error>> {
+        let mut key = String::from("");
+        let mut token = String::from("");
+        let mut url_base = String::from(DEFAULT_URL_BASE);
 
         if config.is_some() {
             let config_object = config.as_ref().unwrap().as_object().unwrap();
-            _key = config_object.get("trello").unwrap().as_object().unwrap().get("developer_api_key").unwrap().as_str().unwrap().to_string();
-            _token = config_object.get("trello").unwrap().as_object().unwrap().get("developer_api_token").unwrap().as_str().unwrap().to_string();
+            key = config_object.get("trello").unwrap().as_object().unwrap().get("developer_api_key").unwrap().as_str().unwrap().to_string();
+            token = config_object.get("trello").unwrap().as_object().unwrap().get("developer_api_token").unwrap().as_str().unwrap().to_string();
+            if let Some(api_url) = config_object.get("trello").unwrap().get("api_url").and_then(|api_url| api_url.as_str()) {
+                url_base = api_url.trim_end_matches('/').to_string();
+            }
         } else {
-            let mut key_file = File::open(PATH_TO_KEY.to_string()).unwrap();
-            key_file.read_to_string(&mut _key).unwrap();
+            let mut key_file = File::open(PATH_TO_KEY)?;
+            key_file.read_to_string(&mut key)?;
+
+            let mut token_file = File::open(PATH_TO_TOKEN)?;
+            token_file.read_to_string(&mut token)?;
+        }
+
+        // Key files usually end with a newline, and the two go into a quoted header
+        let key = key.trim().to_string();
+        let token = token.trim().to_string();
+        for (name, value) in [("key", &key), ("token", &token)] {
+            if value.contains(['"', '\\']) {
+                return Err(Box::new(InvalidInputError {
+                    message: Some(format!("The Trello developer API {} cannot contain quotes or backslashes", name)),
+                }));
+            }
+        }
+
+        let trello_config = config.as_ref().and_then(|config| config.get("trello"));
+        let seconds = |name: &str, default: f64| trello_config.and_then(|trello| trello.get(name)).and_then(|seconds| seconds.as_f64()).unwrap_or(default);
+        // Clones of the client share its connection pool, so every request after the first can reuse a connection
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(Duration::from_secs_f64(seconds("connect_timeout_seconds", DEFAULT_CONNECT_TIMEOUT_SECONDS)))
+            .timeout(Duration::from_secs_f64(seconds("timeout_seconds", DEFAULT_TIMEOUT_SECONDS)))
+            .pool_idle_timeout(Duration::from_secs(POOL_IDLE_TIMEOUT_SECONDS))
+            .tcp_keepalive(Duration::from_secs(POOL_IDLE_TIMEOUT_SECONDS))
+            .build()?;
+
+        let fixtures = Fixtures::from_config(config, vec![key.clone(), token.clone()])?.map(Arc::new);
+        Ok(TrelloDataStore {
+            url_base,
+            key,
+            token,
+            client,
+            rate_limiter: RateLimiter::new(RATE_LIMIT_REQUESTS, RATE_LIMIT_SECONDS),
+            retry_base_delay: Duration::from_millis(RETRY_BASE_DELAY_MILLIS),
+            fixtures,
+        })
+    }
+
+    /**
+     * Sends a request through the fixtures if there are any, and returns the body of a successful response.
+     * Requests Trello rate limits are retried after the delay it asks for, or a growing one. Requests it fails
+     * with a server error are retried too, unless they create something: Trello may have created it before failing.
+     */
+    async fn send(&self, request: TrelloRequest) -> Result<String, Box<dyn std::error::Error>> {
+        let is_idempotent = request.method == Method::GET || request.method == Method::PUT || request.method == Method::DELETE;
+        let mut retries: u32 = 0;
+        loop {
+            let response = self.send_once(&request).await?;
+            let should_retry = response.status == StatusCode::TOO_MANY_REQUESTS || (response.status.is_server_error() && is_idempotent);
+            if should_retry && retries < MAX_RETRIES {
+                tokio::time::sleep(response.retry_after.unwrap_or_else(|| retry_delay(self.retry_base_delay, retries))).await;
+                retries += 1;
+            } else if should_retry {
+                return Err(Box::new(TooManyRetriesError { status: response.status.as_u16(), retries }));
+            } else if response.status.is_success() {
+                return Ok(response.body);
+            } else {
+                return Err(Box::new(TrelloResponseError { status: response.status.as_u16(), message: response.body }));
+            }
+        }
+    }
+
+    async fn send_once(&self, request: &TrelloRequest) -> Result<TrelloResponse, Box<dyn std::error::Error>> {
+        let url_path = request.url_path();
+        if let Some(fixtures) = self.fixtures.as_ref().filter(|fixtures| fixtures.mode() == FixtureMode::Replay) {
+            let (status, response_body) = fixtures.replay(request.method.as_str(), &url_path, request.body.as_ref())?;
+            return Ok(TrelloResponse { status: StatusCode::from_u16(status)?, body: response_body, retry_after: None });
+        }
 
-            let mut token_file = File::open(PATH_TO_TOKEN.to_string()).unwrap();
-            token_file.read_to_string(&mut _token).unwrap();
+        self.rate_limiter.acquire().await;
+        let mut full_url = self.url_base.clone();
+        full_url.push_str(&url_path);
+        let authorization = format!("OAuth oauth_consumer_key=\"{}\", oauth_token=\"{}\"", self.key, self.token);
+        let mut http_request = self.client.request(request.method.clone(), &full_url).header(AUTHORIZATION, authorization);
+        if let Some(body) = &request.body {
+            http_request = http_request.json(body);
         }
+        let response = http_request.send().await?;
+        let status = response.status();
+        let retry_after = response.headers().get(RETRY_AFTER).and_then(|value| value.to_str().ok()).and_then(retry_after);
+        let response_body = response.text().await?;
 
-        unsafe {
-            START.call_once(|| {
-                key = Some(_key);
-                token = Some(_token);
-            });
+        if let Some(fixtures) = &self.fixtures {
+            fixtures.record(request.method.as_str(), &url_path, request.body.as_ref(), status.as_u16(), &response_body)?;
         }
+        Ok(TrelloResponse { status, body: response_body, retry_after })
     }
 
     fn parse_board_from_json(board_json: &Value) -> Result<Board, Box<dyn std::error::Error>> {
@@ -148,6 +376,13 @@ impl TrelloDataStore {
             due_datetime.timestamp()
         };
 
+        let last_activity_instant_seconds: i64 = card_object
+            .get("dateLastActivity")
+            .and_then(|date| date.as_str())
+            .and_then(|date| date.parse::<DateTime<Utc>>().ok())
+            .map(|date| date.timestamp())
+            .unwrap_or(0);
+
         let card = Card {
             _id: ID {
                 trello_id: trello_id,
@@ -163,6 +398,8 @@ impl TrelloDataStore {
             },
             checklists_ids: id_checklists,
             label_ids: id_labels,
+            last_activity_instant_seconds,
+            local_revision: 0,
         };
 
         Ok(card)
@@ -230,6 +467,72 @@ impl TrelloDataStore {
         Ok(checklist)
     }
 
+    fn parse_action_from_json(action_json: &Value) -> Result<BoardAction, Box<dyn std::error::Error>> {
+        let action_object = action_json.as_object().unwrap();
+        let data = action_object.get("data").unwrap();
+        let data_id = |key: &str| {
+            data.get(key).and_then(|item| item.get("id")).and_then(|id| id.as_str()).map(|id| ID {
+                trello_id: Some(String::from(id)),
+                local_id: None,
+            })
+        };
+        let date_instant_seconds: i64 = action_object
+            .get("date")
+            .and_then(|date| date.as_str())
+            .and_then(|date| date.parse::<DateTime<Utc>>().ok())
+            .map(|date| date.timestamp())
+            .unwrap_or(0);
+
+        let action = BoardAction {
+            _id: ID {
+                trello_id: Some(String::from(action_object.get("id").unwrap().as_str().unwrap())),
+                local_id: None,
+            },
+            action_type: String::from(action_object.get("type").unwrap().as_str().unwrap()),
+            board_id: data_id("board").unwrap_or(ID { trello_id: None, local_id: None }),
+            list_ids: ["list", "listBefore", "listAfter"].iter().filter_map(|key| data_id(key)).collect(),
+            card_id: data_id("card"),
+            checklist_id: data_id("checklist"),
+            date_instant_seconds,
+        };
+
+        Ok(action)
+    }
+
+    fn parse_board_snapshot_from_json(board: &Board, board_json: &Value) -> Result<BoardSnapshot, Box<dyn std::error::Error>> {
+        let nested_items = |key: &str| board_json.get(key).and_then(|items| items.as_array()).cloned().unwrap_or_default();
+
+        let mut snapshot = BoardSnapshot {
+            board: board.clone(),
+            labels: vec![],
+            lists: vec![],
+            cards: vec![],
+            checklists: vec![],
+            tasks: vec![],
+            comments: vec![],
+        };
+        for label_json in nested_items("labels") {
+            snapshot.labels.push(TrelloDataStore::parse_label_from_json(&label_json)?);
+        }
+        for list_json in nested_items("lists") {
+            snapshot.lists.push(TrelloDataStore::parse_list_from_json(&list_json)?);
+        }
+        for card_json in nested_items("cards") {
+            snapshot.cards.push(TrelloDataStore::parse_card_from_json(&card_json)?);
+        }
+        for checklist_json in nested_items("checklists") {
+            snapshot.checklists.push(TrelloDataStore::parse_checklist_from_json(&checklist_json)?);
+            for task_json in checklist_json.get("checkItems").and_then(|tasks| tasks.as_array()).into_iter().flatten() {
+                snapshot.tasks.push(TrelloDataStore::parse_checklist_task_from_json(task_json)?);
+            }
+        }
+        for comment_json in nested_items("actions") {
+            snapshot.comments.push(TrelloDataStore::parse_comment_from_json(&comment_json)?);
+        }
+
+        Ok(snapshot)
+    }
+
     fn parse_checklist_task_from_json(checklist_task_json: &Value) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
         let checklist_task_object = checklist_task_json.as_object().unwrap();
         let trello_id = Some(String::from(
@@ -254,6 +557,7 @@ impl TrelloDataStore {
                 local_id: None
             },
             is_complete: is_complete,
+            local_revision: 0,
         };
 
         Ok(checklist_task)
@@ -262,19 +566,9 @@ impl TrelloDataStore {
 
 #[async_trait]
 impl DataStore for TrelloDataStore {
-    async fn get_all_boards() -> Result<Vec<Board>, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/members/me/boards?key={key}&token={token}",
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let trello_response = reqwest::get(&full_url).await?.text().await?;
+    async fn get_all_boards(&self) -> Result<Vec<Board>, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::GET, &["members", "me", "boards"]);
+        let trello_response = self.send(request).await?;
 
         let boards: Value = serde_json::from_str(&trello_response)?;
 
@@ -287,42 +581,22 @@ impl DataStore for TrelloDataStore {
         Ok(result)
     }
 
-    async fn create_board(name: &str, _trello_id: Option<String>) -> Result<Board, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/boards?key={key}&token={token}&name={board_name}&defaultLabels=false",
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-                board_name = name
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let trello_response = client.post(&full_url).send().await?.text().await?;
+    async fn create_board(&self, name: &str, _trello_id: Option<String>) -> Result<Board, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::POST, &["boards"])
+            .query("name", name)
+            .query("defaultLabels", "false");
+        let trello_response = self.send(request).await?;
 
         let board_serde: Value = serde_json::from_str(&trello_response)?;
         TrelloDataStore::parse_board_from_json(&board_serde)
     }
 
     async fn get_all_board_labels(
+        &self,
         board_id: ID,
     ) -> Result<Vec<CardLabel>, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/boards/{id}/labels?key={key}&token={token}",
-                id = board_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let trello_response = reqwest::get(&full_url).await?.text().await?;
+        let request = TrelloRequest::new(Method::GET, &["boards", &board_id.trello_id.unwrap(), "labels"]);
+        let trello_response = self.send(request).await?;
 
         let labels: Value = serde_json::from_str(&trello_response)?;
 
@@ -335,101 +609,44 @@ impl DataStore for TrelloDataStore {
         Ok(result)
     }
 
-    async fn delete_board_label(label_id: ID) -> Result<(), Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/labels/{id}?key={key}&token={token}",
-                id = label_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.delete(&full_url).send().await?;
-        let response_status = response.status();
-        if response_status.is_success() {
-            Ok(())
-        } else {
-            Err(Box::new(InvalidInputError {
-                message: Some(format!(
-                    "Failed to delete label. {}",
-                    response_status.canonical_reason().unwrap_or("")
-                )),
-            }))
-        }
+    async fn delete_board_label(&self, label_id: ID) -> Result<(), Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::DELETE, &["labels", &label_id.trello_id.unwrap()]);
+        self.send(request).await?;
+        Ok(())
     }
 
     async fn update_board_label(
+        &self,
         label_id: ID,
         name: &str,
         color: &str,
     ) -> Result<CardLabel, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/labels/{id}?key={key}&token={token}&name={name}&color={color}",
-                id = label_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-                name = name,
-                color = color
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.put(&full_url).send().await?;
-        let response_text = response.text().await?;
+        let request = TrelloRequest::new(Method::PUT, &["labels", &label_id.trello_id.unwrap()])
+            .query("name", name)
+            .query("color", color);
+        let response_text = self.send(request).await?;
         let label_json: Value = serde_json::from_str(&response_text)?;
         TrelloDataStore::parse_label_from_json(&label_json)
     }
 
     async fn create_board_label(
+        &self,
         board_id: ID,
         name: &str,
-        color: &str, 
-        _trello_id: Option<String>
+        color: &str,
+        _trello_id: Option<String>,
     ) -> Result<CardLabel, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/boards/{board_id}/labels?key={key}&token={token}&name={name}&color={color}",
-                board_id = board_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-                name = name,
-                color = color
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.post(&full_url).send().await?;
-        let response_text = response.text().await?;
+        let request = TrelloRequest::new(Method::POST, &["boards", &board_id.trello_id.unwrap(), "labels"])
+            .query("name", name)
+            .query("color", color);
+        let response_text = self.send(request).await?;
         let label_json: Value = serde_json::from_str(&response_text)?;
         TrelloDataStore::parse_label_from_json(&label_json)
     }
 
-    async fn get_all_board_lists(board_id: ID) -> Result<Vec<BoardList>, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/boards/{id}/lists?key={key}&token={token}",
-                id = board_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let trello_response = reqwest::get(&full_url).await?.text().await?;
+    async fn get_all_board_lists(&self, board_id: ID) -> Result<Vec<BoardList>, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::GET, &["boards", &board_id.trello_id.unwrap(), "lists"]);
+        let trello_response = self.send(request).await?;
 
         let lists: Value = serde_json::from_str(&trello_response)?;
 
@@ -442,41 +659,17 @@ impl DataStore for TrelloDataStore {
         Ok(result)
     }
 
-    async fn create_board_list(board_id: ID, name: &str, _trello_id: Option<String>) -> Result<BoardList, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/boards/{board_id}/lists?key={key}&token={token}&name={name}",
-                board_id = board_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-                name = name,
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.post(&full_url).send().await?;
-        let response_text = response.text().await?;
+    async fn create_board_list(&self, board_id: ID, name: &str, _trello_id: Option<String>) -> Result<BoardList, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::POST, &["boards", &board_id.trello_id.unwrap(), "lists"])
+            .query("name", name);
+        let response_text = self.send(request).await?;
         let list_json: Value = serde_json::from_str(&response_text)?;
         TrelloDataStore::parse_list_from_json(&list_json)
     }
 
-    async fn get_all_list_cards(list_id: ID) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/lists/{id}/cards?key={key}&token={token}",
-                id = list_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let trello_response = reqwest::get(&full_url).await?.text().await?;
+    async fn get_all_list_cards(&self, list_id: ID) -> Result<Vec<Card>, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::GET, &["lists", &list_id.trello_id.unwrap(), "cards"]);
+        let trello_response = self.send(request).await?;
 
         let cards: Value = serde_json::from_str(&trello_response)?;
 
@@ -489,39 +682,25 @@ impl DataStore for TrelloDataStore {
         Ok(result)
     }
 
-    async fn create_list_card(list_id: ID, name: &str, _trello_id: Option<String>) -> Result<Card, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/cards/?idList={id}&key={key}&token={token}&name={name}",
-                id = list_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-                name = name,
-            );
-        }
+    async fn get_card(&self, card_id: ID) -> Result<Card, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::GET, &["cards", &card_id.trello_id.unwrap()]);
+        let trello_response = self.send(request).await?;
 
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.post(&full_url).send().await?;
-        let response_text = response.text().await?;
+        let card_json: Value = serde_json::from_str(&trello_response)?;
+        TrelloDataStore::parse_card_from_json(&card_json)
+    }
+
+    async fn create_list_card(&self, list_id: ID, name: &str, _trello_id: Option<String>) -> Result<Card, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::POST, &["cards"])
+            .query("idList", list_id.trello_id.unwrap())
+            .query("name", name);
+        let response_text = self.send(request).await?;
         let card_json: Value = serde_json::from_str(&response_text)?;
 
         TrelloDataStore::parse_card_from_json(&card_json)
     }
 
-    async fn update_card(card: &Card) -> Result<Card, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/cards/{id}?key={key}&token={token}",
-                id = card._id.trello_id.clone().unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
+    async fn update_card(&self, card: &Card) -> Result<Card, Box<dyn std::error::Error>> {
         let label_trello_ids = card.label_ids.iter().map(|id| id.trello_id.clone().unwrap_or("".to_string())).collect::<Vec<_>>();
         let due_date: Option<DateTime<Utc>> = if card.due_date_instant_seconds == 0 {
             None
@@ -546,30 +725,20 @@ impl DataStore for TrelloDataStore {
             "dueComplete": card.due_complete
         });
 
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.put(&full_url).json(&request_body).send().await?;
-        let response_text = response.text().await?;
+        let request = TrelloRequest::new(Method::PUT, &["cards", &card._id.trello_id.clone().unwrap()])
+            .json(request_body);
+        let response_text = self.send(request).await?;
         let card_json: Value = serde_json::from_str(&response_text)?;
         TrelloDataStore::parse_card_from_json(&card_json)
     }
 
-    async fn get_card_comments(card_id: ID) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
+    async fn get_card_comments(&self, card_id: ID) -> Result<Vec<CardComment>, Box<dyn std::error::Error>> {
 
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/cards/{id}/actions?key={key}&token={token}&filter=commentCard",
-                id = card_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let trello_response = reqwest::get(&full_url).await?.text().await?;
+        // Trello returns the latest 50 actions unless asked for more, and a sync treats comments it did not get as deleted
+        let request = TrelloRequest::new(Method::GET, &["cards", &card_id.trello_id.unwrap(), "actions"])
+            .query("filter", "commentCard")
+            .query("limit", CARD_COMMENTS_LIMIT);
+        let trello_response = self.send(request).await?;
 
         let comments: Value = serde_json::from_str(&trello_response)?;
 
@@ -582,44 +751,21 @@ impl DataStore for TrelloDataStore {
         Ok(result)
     }
 
-    async fn add_card_comment(card_id: ID, text: &str, _trello_id: Option<String>) -> Result<CardComment, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/cards/{id}/actions/comments?key={key}&token={token}",
-                id = card_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
+    async fn add_card_comment(&self, card_id: ID, text: &str, _trello_id: Option<String>) -> Result<CardComment, Box<dyn std::error::Error>> {
         let request_body = json!({
             "text": text
         });
 
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.post(&full_url).json(&request_body).send().await?;
-        let response_text = response.text().await?;
+        let request = TrelloRequest::new(Method::POST, &["cards", &card_id.trello_id.unwrap(), "actions", "comments"])
+            .json(request_body);
+        let response_text = self.send(request).await?;
         let comment_json: Value = serde_json::from_str(&response_text)?;
         TrelloDataStore::parse_comment_from_json(&comment_json)
     }
 
-    async fn get_card_checklists(card_id: ID) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/cards/{id}/checklists?key={key}&token={token}",
-                id = card_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let trello_response = reqwest::get(&full_url).await?.text().await?;
+    async fn get_card_checklists(&self, card_id: ID) -> Result<Vec<CardChecklist>, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::GET, &["cards", &card_id.trello_id.unwrap(), "checklists"]);
+        let trello_response = self.send(request).await?;
 
         let checklists: Value = serde_json::from_str(&trello_response)?;
 
@@ -633,44 +779,21 @@ impl DataStore for TrelloDataStore {
 
     }
 
-    async fn create_card_checklist(card_id: ID, name: &str, _trello_id: Option<String>) -> Result<CardChecklist, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/cards/{id}/checklists?key={key}&token={token}",
-                id = card_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
+    async fn create_card_checklist(&self, card_id: ID, name: &str, _trello_id: Option<String>) -> Result<CardChecklist, Box<dyn std::error::Error>> {
         let request_body = json!({
             "name": name
         });
 
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.post(&full_url).json(&request_body).send().await?;
-        let response_text = response.text().await?;
+        let request = TrelloRequest::new(Method::POST, &["cards", &card_id.trello_id.unwrap(), "checklists"])
+            .json(request_body);
+        let response_text = self.send(request).await?;
         let checklist_json: Value = serde_json::from_str(&response_text)?;
         TrelloDataStore::parse_checklist_from_json(&checklist_json)
     }
 
-    async fn get_checklist_tasks(checklist_id: ID) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/checklists/{id}/checkItems?key={key}&token={token}",
-                id = checklist_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let trello_response = reqwest::get(&full_url).await?.text().await?;
+    async fn get_checklist_tasks(&self, checklist_id: ID) -> Result<Vec<CardChecklistTask>, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::GET, &["checklists", &checklist_id.trello_id.unwrap(), "checkItems"]);
+        let trello_response = self.send(request).await?;
 
         let tasks: Value = serde_json::from_str(&trello_response)?;
 
@@ -683,42 +806,19 @@ impl DataStore for TrelloDataStore {
         Ok(result)
     }
 
-    async fn create_checklist_task(checklist_id: ID, name: &str, _trello_id: Option<String>) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/checklists/{id}/checkItems?key={key}&token={token}",
-                id = checklist_id.trello_id.unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
+    async fn create_checklist_task(&self, checklist_id: ID, name: &str, _trello_id: Option<String>) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
         let request_body = json!({
             "name": name
         });
 
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.post(&full_url).json(&request_body).send().await?;
-        let response_text = response.text().await?;
+        let request = TrelloRequest::new(Method::POST, &["checklists", &checklist_id.trello_id.unwrap(), "checkItems"])
+            .json(request_body);
+        let response_text = self.send(request).await?;
         let task_json: Value = serde_json::from_str(&response_text)?;
         TrelloDataStore::parse_checklist_task_from_json(&task_json)
     }
 
-    async fn update_checklist_task(card_id: ID, task: &CardChecklistTask) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
-        let url_path: String;
-        unsafe {
-            url_path = format!(
-                "/cards/{card_id}/checkitem/{task_id}?key={key}&token={token}",
-                card_id = card_id.trello_id.unwrap(),
-                task_id = task._id.trello_id.clone().unwrap(),
-                key = key.clone().unwrap(),
-                token = token.clone().unwrap(),
-            );
-        }
-
+    async fn update_checklist_task(&self, card_id: ID, task: &CardChecklistTask) -> Result<CardChecklistTask, Box<dyn std::error::Error>> {
         let state = if task.is_complete {
             "complete"
         } else {
@@ -729,30 +829,307 @@ impl DataStore for TrelloDataStore {
             "state": state
         });
 
-        let mut full_url = String::from(URL_BASE);
-        full_url.push_str(&url_path);
-        let client = reqwest::Client::new();
-        let response = client.put(&full_url).json(&request_body).send().await?;
-        let response_text = response.text().await?;
+        let request = TrelloRequest::new(Method::PUT, &["cards", &card_id.trello_id.unwrap(), "checkitem", &task._id.trello_id.clone().unwrap()])
+            .json(request_body);
+        let response_text = self.send(request).await?;
         let task_json: Value = serde_json::from_str(&response_text)?;
         TrelloDataStore::parse_checklist_task_from_json(&task_json)
     }
+
+    async fn get_board_actions(&self, board_id: ID, since: &str) -> Result<Option<Vec<BoardAction>>, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::GET, &["boards", &board_id.trello_id.unwrap(), "actions"])
+            .query("since", since)
+            .query("limit", BOARD_ACTIONS_LIMIT);
+        let trello_response = self.send(request).await?;
+
+        let actions: Value = serde_json::from_str(&trello_response)?;
+        let actions = actions.as_array().unwrap();
+        if actions.len() >= BOARD_ACTIONS_LIMIT {
+            return Ok(None);
+        }
+
+        let mut result: Vec<BoardAction> = Vec::new();
+        for action_json in actions {
+            result.push(TrelloDataStore::parse_action_from_json(action_json)?);
+        }
+
+        Ok(Some(result))
+    }
+
+    async fn get_board_snapshot(&self, board: &Board) -> Result<BoardSnapshot, Box<dyn std::error::Error>> {
+        let request = TrelloRequest::new(Method::GET, &["boards", &board._id.trello_id.clone().unwrap()])
+            .query("fields", "name")
+            .query("labels", "all")
+            .query("labels_limit", "1000")
+            .query("lists", "open")
+            .query("cards", "open")
+            .query("checklists", "all")
+            .query("actions", "commentCard")
+            .query("actions_limit", BOARD_COMMENTS_LIMIT)
+            .query("action_memberCreator_fields", "fullName");
+        let trello_response = self.send(request).await?;
+
+        let board_json: Value = serde_json::from_str(&trello_response)?;
+        let mut snapshot = TrelloDataStore::parse_board_snapshot_from_json(board, &board_json)?;
+
+        if snapshot.comments.len() >= BOARD_COMMENTS_LIMIT {
+            // Some comments were cut off, and a sync treats comments it did not get as deleted
+            snapshot.comments.clear();
+            for card in &snapshot.cards {
+                let card_comments = self.get_card_comments(card._id.clone()).await?;
+                snapshot.comments.extend(card_comments);
+            }
+        }
+
+        Ok(snapshot)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::mock_trello::MockTrello;
+
+    fn replay_config(path: &str) -> Option<Value> {
+        Some(json!({
+            "trello": {
+                "developer_api_key": "some-key",
+                "developer_api_token": "some-token",
+                "fixtures": { "mode": "replay", "path": path }
+            }
+        }))
+    }
 
     #[test]
     fn init() {
-        unsafe {
-            println!(
-                "Key: {:?}\nToken: {:?}",
-                key.clone().unwrap(),
-                token.clone().unwrap()
-            );
-            assert_ne!(key.clone().unwrap(), String::from(""));
-            assert_ne!(token.clone().unwrap(), String::from(""));
+        let config = Some(json!({
+            "trello": {
+                "developer_api_key": "some-key",
+                "developer_api_token": "some-token"
+            }
+        }));
+
+        let store = TrelloDataStore::new(&config).unwrap();
+        assert_eq!(store.key, "some-key");
+        assert_eq!(store.token, "some-token");
+        assert_eq!(store.url_base, DEFAULT_URL_BASE);
+    }
+
+    #[test]
+    fn credentials_are_trimmed_and_checked() {
+        let config = Some(json!({
+            "trello": {
+                "developer_api_key": " some-key\n",
+                "developer_api_token": "some-token\n"
+            }
+        }));
+        let store = TrelloDataStore::new(&config).unwrap();
+        assert_eq!(store.key, "some-key");
+        assert_eq!(store.token, "some-token");
+
+        let config = Some(json!({
+            "trello": {
+                "developer_api_key": "some-key",
+                "developer_api_token": "some-\"token"
+            }
+        }));
+        assert!(TrelloDataStore::new(&config).is_err());
+    }
+
+    #[test]
+    fn api_url_comes_from_config() {
+        let config = Some(json!({
+            "trello": {
+                "developer_api_key": "some-key",
+                "developer_api_token": "some-token",
+                "api_url": "http://localhost:8080/1/"
+            }
+        }));
+        let store = TrelloDataStore::new(&config).unwrap();
+        assert_eq!(store.url_base, "http://localhost:8080/1");
+    }
+
+    #[test]
+    fn board_snapshot_is_parsed_from_nested_resources() {
+        let board = Board { _id: ID { trello_id: Some(String::from("b1")), local_id: Some(String::from("local")) }, name: String::from("Eng") };
+        let board_json = json!({
+            "id": "b1",
+            "name": "Eng",
+            "labels": [{ "id": "l1", "idBoard": "b1", "name": "Bug", "color": "red" }],
+            "lists": [{ "id": "li1", "idBoard": "b1", "name": "Todo" }],
+            "cards": [{
+                "id": "c1", "name": "Fix login", "idList": "li1", "idChecklists": ["ch1"], "idLabels": ["l1"],
+                "desc": "", "dueComplete": false, "due": null, "dateLastActivity": "2021-06-01T10:00:00.000Z"
+            }],
+            "checklists": [{
+                "id": "ch1", "idCard": "c1", "name": "Steps",
+                "checkItems": [{ "id": "t1", "idChecklist": "ch1", "name": "Reproduce", "state": "complete" }]
+            }],
+            "actions": [{
+                "id": "a1", "date": "2021-06-01T10:00:00.000Z", "memberCreator": { "fullName": "Ada" },
+                "data": { "text": "Seen on staging", "card": { "id": "c1" } }
+            }]
+        });
+
+        let snapshot = TrelloDataStore::parse_board_snapshot_from_json(&board, &board_json).unwrap();
+        assert_eq!(snapshot.board._id.local_id, Some(String::from("local")));
+        assert_eq!(snapshot.describe(), "1 list(s), 1 card(s), 1 label(s), 1 checklist(s), 1 task(s) and 1 comment(s)");
+        assert!(snapshot.tasks[0].is_complete);
+        assert_eq!(snapshot.comments[0].card_id.trello_id, Some(String::from("c1")));
+    }
+
+    #[test]
+    fn action_keeps_the_ids_it_touched() {
+        let action_json = json!({
+            "id": "a1",
+            "type": "updateCard",
+            "date": "2021-06-01T10:00:00.000Z",
+            "data": { "board": { "id": "b1" }, "card": { "id": "c1" }, "listBefore": { "id": "l1" }, "listAfter": { "id": "l2" } }
+        });
+
+        let action = TrelloDataStore::parse_action_from_json(&action_json).unwrap();
+        assert_eq!(action.action_type, "updateCard");
+        assert_eq!(action.board_id.trello_id, Some(String::from("b1")));
+        let list_ids: Vec<Option<String>> = action.list_ids.into_iter().map(|id| id.trello_id).collect();
+        assert_eq!(list_ids, vec![Some(String::from("l1")), Some(String::from("l2"))]);
+        assert!(action.checklist_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn requests_are_recorded_without_credentials_and_replayed() {
+        let path = std::env::temp_dir().join(format!("trello-cli-fixtures-{}.json", std::process::id()));
+        let trello = MockTrello::start().await;
+        let mut config = trello.config().unwrap();
+        config["trello"]["fixtures"] = json!({ "mode": "record", "path": path });
+        let recording_store = TrelloDataStore::new(&Some(config)).unwrap();
+        let board = recording_store.create_board("Eng", None).await.unwrap();
+        recording_store.create_board_list(board._id.clone(), "In Progress", None).await.unwrap();
+        trello.stop().await;
+
+        let recording = std::fs::read_to_string(&path).unwrap();
+        assert!(!recording.contains("mock-key") && !recording.contains("mock-token"));
+        let replaying_store = TrelloDataStore::new(&replay_config(path.to_str().unwrap())).unwrap();
+        assert_eq!(replaying_store.create_board("Eng", None).await.unwrap()._id.trello_id, board._id.trello_id);
+        let list = replaying_store.create_board_list(board._id.clone(), "In Progress", None).await.unwrap();
+        assert_eq!(list.name, "In Progress");
+        assert!(replaying_store.create_board("Eng", None).await.is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cards_parse_from_recorded_trello_payloads() {
+        let store = TrelloDataStore::new(&replay_config(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/trello/card.json"))).unwrap();
+        let card_id = ID { trello_id: Some(String::from("60b5e1c27c1a2b3c4d5e6f72")), local_id: None };
+
+        let card = store.get_card(card_id.clone()).await.unwrap();
+        assert_eq!(card.name, "Fix login with SSO");
+        assert_eq!(card.due_date_instant_seconds, Utc.with_ymd_and_hms(2021, 6, 4, 16, 0, 0).unwrap().timestamp());
+        assert_eq!(card.label_ids.len(), 1);
+        assert_eq!(card.checklists_ids.len(), 1);
+        assert!(card.last_activity_instant_seconds > 0);
+
+        let comments = store.get_card_comments(card_id.clone()).await.unwrap();
+        assert_eq!(comments[0].commenter_name, "Ada Lovelace");
+        assert_eq!(comments[0].text, "Reproduced with a fresh account");
+
+        let checklists = store.get_card_checklists(card_id).await.unwrap();
+        let tasks = store.get_checklist_tasks(checklists[0]._id.clone()).await.unwrap();
+        let completed: Vec<bool> = tasks.iter().map(|task| task.is_complete).collect();
+        assert_eq!(completed, vec![true, false]);
+    }
+
+    #[tokio::test]
+    async fn requests_time_out_and_identify_the_cli() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Some(json!({
+            "trello": {
+                "developer_api_key": "some-key",
+                "developer_api_token": "some-token",
+                "api_url": format!("http://{}/1", listener.local_addr().unwrap()),
+                "timeout_seconds": 0.2
+            }
+        }));
+        let (request_sender, request_receiver) = tokio::sync::oneshot::channel();
+        let stalled_server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0_u8; 1024];
+            let read = tokio::io::AsyncReadExt::read(&mut stream, &mut request).await.unwrap();
+            let _ = request_sender.send(String::from_utf8_lossy(&request[..read]).to_lowercase());
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let store = TrelloDataStore::new(&config).unwrap();
+        let why = store.get_all_boards().await.err().unwrap();
+        assert!(why.downcast_ref::<reqwest::Error>().unwrap().is_timeout());
+        assert!(request_receiver.await.unwrap().contains("user-agent: trello-cli/"));
+        stalled_server.abort();
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_are_retried_until_retries_run_out() {
+        let trello = MockTrello::start().await;
+        let mut store = TrelloDataStore::new(&trello.config()).unwrap();
+        store.retry_base_delay = Duration::from_millis(1);
+        store.create_board("Eng", None).await.unwrap();
+
+        trello.fail_next(&[429, 503]);
+        assert_eq!(store.get_all_boards().await.unwrap().len(), 1);
+
+        // Creating is not retried after a server error, Trello may have created the board already
+        trello.fail_next(&[500]);
+        assert!(store.create_board("Ops", None).await.err().unwrap().is::<TrelloResponseError>());
+
+        trello.fail_next(&[429; MAX_RETRIES as usize + 1]);
+        let why = store.get_all_boards().await.err().unwrap();
+        assert!(why.is::<TooManyRetriesError>());
+        assert!(why.to_string().contains("after 4 retries"));
+    }
+
+    #[tokio::test]
+    async fn requests_wait_for_the_rate_limit() {
+        let rate_limiter = RateLimiter::new(2.0, 0.1);
+        let started = Instant::now();
+        for _ in 0..4 {
+            rate_limiter.acquire().await;
         }
+        assert!(started.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn request_parameters_are_encoded() {
+        let request = TrelloRequest::new(Method::POST, &["boards", "b1", "labels"]).query("name", "R&D #1 + café").query("color", "red");
+        assert_eq!(request.url_path(), "/boards/b1/labels?name=R%26D%20%231%20%2B%20caf%C3%A9&color=red");
+    }
+
+    #[tokio::test]
+    async fn names_survive_the_trip_and_errors_are_checked() {
+        let trello = MockTrello::start().await;
+        let store = TrelloDataStore::new(&trello.config()).unwrap();
+        let board = store.create_board("R&D #1 + café", None).await.unwrap();
+        assert_eq!(board.name, "R&D #1 + café");
+
+        let missing_card = ID { trello_id: Some(String::from("missing")), local_id: None };
+        let why = store.get_card(missing_card).await.err().unwrap();
+        assert!(why.is::<TrelloResponseError>());
+        assert!(why.to_string().contains("no such item"));
+
+        let mut config = trello.config().unwrap();
+        config["trello"]["developer_api_token"] = json!("revoked-token");
+        let why = TrelloDataStore::new(&Some(config)).unwrap().get_all_boards().await.err().unwrap();
+        assert!(why.to_string().contains("did not accept the developer API key and token"));
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_wait_as_long_as_trello_asks() {
+        let trello = MockTrello::start().await;
+        let mut store = TrelloDataStore::new(&trello.config()).unwrap();
+        store.retry_base_delay = Duration::from_secs(60);
+
+        // The mock asks to retry right away, so this only finishes in time if that is what the store does
+        trello.fail_next(&[429]);
+        let boards = tokio::time::timeout(Duration::from_secs(5), store.get_all_boards()).await.unwrap();
+        assert!(boards.unwrap().is_empty());
+        assert_eq!(retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::from_secs(0)));
     }
 }
@@ -3,7 +3,7 @@ extern crate lazy_static;
 use mongodb::{Client, options::ClientOptions };
 use structopt::StructOpt;
 use tokio;
-use serde_json::Value;
+use serde_json::{json, Value};
 use dirs;
 
 use std::path::PathBuf;
@@ -27,7 +27,26 @@ struct CliArgs {
     key_file: Option<PathBuf>,
 
     #[structopt(short = "c", long = "config", parse(from_os_str))]
-    config_file: Option<PathBuf>
+    config_file: Option<PathBuf>,
+
+    /// Output format for command results: text, json, yaml or tsv
+    #[structopt(short = "o", long = "output", default_value = "text", possible_values = &["text", "json", "yaml", "tsv"])]
+    output: control::output::OutputFormat,
+
+    /// Run shell commands from a script file ("-" reads the script from stdin)
+    #[structopt(short = "s", long = "script", parse(from_os_str))]
+    script_file: Option<PathBuf>,
+
+    /// Abort the script at the first command that fails
+    #[structopt(long = "stop-on-error")]
+    stop_on_error: bool,
+
+    /// Keep everything in memory instead of talking to Trello and the local store. Nothing is saved
+    #[structopt(long)]
+    ephemeral: bool,
+
+    #[structopt(subcommand)]
+    command: Option<control::command_line::Command>
 }
 
 #[tokio::main]
@@ -39,13 +58,15 @@ async fn main() {
     let mut app_config_folder: PathBuf = config_folder.unwrap_or_default();
     app_config_folder.push("trello-cli");
     let mut default_app_config_path: PathBuf = app_config_folder.clone();
+    let mut history_path: PathBuf = app_config_folder.clone();
+    history_path.push("history.txt");
 
     if !app_config_folder.is_dir() {
         let create_result = std::fs::create_dir_all(app_config_folder);
         match create_result {
-            Ok(()) => println!("Created config dir"),
+            Ok(()) => eprintln!("Created config dir"),
             Err(e) => {
-                println!("Failed to create config directory");
+                eprintln!("Failed to create config directory");
                 return;
             }
         };
@@ -66,16 +87,59 @@ async fn main() {
         let mut file_content = String::from("");
         config_file.read_to_string(&mut file_content).unwrap();
         config_object = Some(serde_json::from_str(&file_content).unwrap());
-    } else {
-        println!("No config file provided and no config.json file was found at {}", default_app_config_path.display());
+    } else if !args.ephemeral {
+        eprintln!("No config file provided and no config.json file was found at {}", default_app_config_path.display());
+    }
+
+    if args.ephemeral {
+        let mut config = config_object.unwrap_or_else(|| json!({}));
+        config["stores"] = json!({ "remote": "memory", "local": "memory" });
+        config_object = Some(config);
+    }
+
+    if let Some(script_path) = args.script_file {
+        let result_code = if script_path.as_os_str() == "-" {
+            let stdin = std::io::stdin();
+            control::interactive_cli::run_script(config_object, args.output, stdin.lock(), args.stop_on_error).await
+        } else {
+            match File::open(&script_path) {
+                Ok(script_file) => {
+                    let reader = std::io::BufReader::new(script_file);
+                    control::interactive_cli::run_script(config_object, args.output, reader, args.stop_on_error).await
+                }
+                Err(why) => {
+                    eprintln!("Failed to open script {}: {}", script_path.display(), why);
+                    control::CommandResultCode::Failed
+                }
+            }
+        };
+
+        if let control::CommandResultCode::Failed = result_code {
+            std::process::exit(result_code.exit_code());
+        }
+        return
     }
 
     if args.interactive {
         // Do something
-        control::interactive_cli::run(config_object).await;
+        let result_code = control::interactive_cli::run(config_object, args.output, args.stop_on_error, history_path).await;
+        if let control::CommandResultCode::Failed = result_code {
+            std::process::exit(result_code.exit_code());
+        }
         return
     }
 
+    if let Some(command) = args.command {
+        let result_code = control::command_line::run(config_object, command, args.output).await;
+        if let control::CommandResultCode::Failed = result_code {
+            std::process::exit(result_code.exit_code());
+        }
+        return
+    }
+
+    CliArgs::clap().print_help().unwrap();
+    println!();
+
     // let mongo_result = test_mongo_connection().await;
     // assert_eq!(mongo_result.is_ok(), true);
     // println!();
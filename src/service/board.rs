@@ -6,9 +6,57 @@ pub struct BoardService {
 }
 
 impl BoardService {
-    pub async fn new(config: Option<serde_json::Value>) -> BoardService {
-        let dr = DataRepository::new(config);
-        BoardService { data_repo: dr.await.unwrap() }
+    pub async fn new(config: Option<serde_json::Value>) -> Result<BoardService, Box<dyn std::error::Error>> {
+        let dr = DataRepository::new(config).await?;
+        Ok(BoardService { data_repo: dr })
+    }
+
+    pub fn deselect(&mut self, board: bool, list: bool, card: bool, checklist: bool) {
+        self.data_repo.deselect(board, list, card, checklist)
+    }
+
+    pub fn restore_selection(&mut self, board: Option<Board>, list: Option<BoardList>, card: Option<Card>, checklist: Option<CardChecklist>) {
+        self.data_repo.restore_selection(board, list, card, checklist)
+    }
+
+    pub fn get_cached_names(&self) -> CachedNames {
+        self.data_repo.get_cached_names()
+    }
+
+    pub fn local_read_last_synced(&self) -> Option<i64> {
+        self.data_repo.local_read_last_synced()
+    }
+
+    pub async fn push_outbox(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        self.data_repo.push_outbox().await
+    }
+
+    pub async fn sync_board(&mut self, name: &str, full: bool) -> Result<BoardSnapshot, Box<dyn std::error::Error>> {
+        self.data_repo.sync_board(name, full).await
+    }
+
+    pub async fn sync_all_boards(&mut self, full: bool) -> Result<Vec<BoardSnapshot>, Box<dyn std::error::Error>> {
+        self.data_repo.sync_all_boards(full).await
+    }
+
+    pub async fn migrate_local_store(&self, dry_run: bool) -> Result<MigrationReport, Box<dyn std::error::Error>> {
+        self.data_repo.migrate_local_store(dry_run).await
+    }
+
+    pub async fn purge_tombstones(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        self.data_repo.purge_tombstones().await
+    }
+
+    pub async fn get_conflicts(&self) -> Result<Vec<SyncConflict>, Box<dyn std::error::Error>> {
+        self.data_repo.get_conflicts().await
+    }
+
+    pub async fn resolve_conflict(&mut self, index: usize, use_local: bool, fields: &[String]) -> Result<SyncConflict, Box<dyn std::error::Error>> {
+        self.data_repo.resolve_conflict(index, use_local, fields).await
+    }
+
+    pub fn take_recorded_conflicts(&mut self) -> usize {
+        self.data_repo.take_recorded_conflicts()
     }
 
     pub async fn get_all_boards(&mut self) -> Result<Vec<Board>, Box<dyn std::error::Error>> {